@@ -0,0 +1,451 @@
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashSet, VecDeque},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::game::{
+    action::Action,
+    matrix::{Cell, Color, Matrix},
+    piece::{Piece, Point},
+    Game,
+    Phase,
+};
+
+// The moves tried from each position while searching
+const MOVES: [Action; 6] = [
+    Action::MoveLeft,
+    Action::MoveRight,
+    Action::SoftDrop,
+    Action::RotateClockwise,
+    Action::RotateCounterclockwise,
+    Action::Rotate180,
+];
+
+/// Somewhere the piece can come to rest, and the inputs that take it there
+/// from where it started, ending with the soft drop that locks it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Placement {
+    pub piece: Piece,
+    pub inputs: Vec<Action>,
+}
+
+/// How much each feature of the board after a placement counts towards
+/// its score. The defaults are Pierre Dellacherie's, as tuned for El-Tetris;
+/// aggregate height and bumpiness are left out of those.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Weights {
+    pub landing_height: f64,
+    pub lines_cleared: f64,
+    pub row_transitions: f64,
+    pub column_transitions: f64,
+    pub holes: f64,
+    pub wells: f64,
+    pub aggregate_height: f64,
+    pub bumpiness: f64,
+}
+
+impl Default for Weights {
+    fn default() -> Self {
+        Self {
+            landing_height: -4.500158825082766,
+            lines_cleared: 3.4181268101392694,
+            row_transitions: -3.2178882868487753,
+            column_transitions: -9.348695305445199,
+            holes: -7.899265427351652,
+            wells: -3.3855972247263626,
+            aggregate_height: 0.0,
+            bumpiness: 0.0,
+        }
+    }
+}
+
+impl Weights {
+    pub fn score(&self, features: &Features) -> f64 {
+        self.landing_height * features.landing_height
+            + self.lines_cleared * features.lines_cleared
+            + self.row_transitions * features.row_transitions
+            + self.column_transitions * features.column_transitions
+            + self.holes * features.holes
+            + self.wells * features.wells
+            + self.aggregate_height * features.aggregate_height
+            + self.bumpiness * features.bumpiness
+    }
+}
+
+/// What the board looks like once a piece is locked and its rows cleared
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Features {
+    // how high the middle of the piece came to rest
+    pub landing_height: f64,
+    pub lines_cleared: f64,
+    // filled cells next to empty ones along the rows, the walls counting as filled
+    pub row_transitions: f64,
+    // the same up the columns, with the floor filled
+    pub column_transitions: f64,
+    // empty cells with a filled one somewhere above
+    pub holes: f64,
+    // each open cell down a well adds its depth so far, holes aside
+    pub wells: f64,
+    // the column heights added up
+    pub aggregate_height: f64,
+    // the height differences between neighbouring columns added up
+    pub bumpiness: f64,
+}
+
+impl Features {
+    // The piece is expected to fit where it is on the board
+    pub fn new(board: &Matrix<Cell>, piece: &Piece) -> Self {
+        let (width, height) = (board.width(), board.height());
+        let mut filled: Vec<Vec<bool>> = board.rows
+            .iter()
+            .map(|row| row.iter().map(|cell| cell.value == Color::Black).collect())
+            .collect();
+        piece.points.iter().for_each(|p| filled[p.y as usize][p.x as usize] = true);
+        let (low, high) = piece.bounds();
+        filled.retain(|row| !row.iter().all(|cell| *cell));
+        let lines_cleared = height - filled.len();
+        filled.resize(height, vec![false; width]);
+
+        let is_filled = |x: i32, y: i32| {
+            x < 0 || x >= width as i32 || y < 0 || filled[y as usize][x as usize]
+        };
+        let heights: Vec<usize> = (0..width)
+            .map(|x| (0..height).rev().find(|y| filled[*y][x]).map_or(0, |y| y + 1))
+            .collect();
+        let mut features = Features {
+            landing_height: (low.y + high.y) as f64 / 2.0,
+            lines_cleared: lines_cleared as f64,
+            aggregate_height: heights.iter().sum::<usize>() as f64,
+            bumpiness: heights.windows(2).map(|pair| pair[0].abs_diff(pair[1])).sum::<usize>() as f64,
+            ..Features::default()
+        };
+        for y in 0..height as i32 {
+            // the row transitions, counting the wall at the far side
+            features.row_transitions += (0..=width as i32)
+                .filter(|x| is_filled(*x - 1, y) != is_filled(*x, y))
+                .count() as f64;
+        }
+        for (x, column_height) in heights.iter().enumerate() {
+            let x = x as i32;
+            features.column_transitions += (0..height as i32)
+                .filter(|y| is_filled(x, *y - 1) != is_filled(x, *y))
+                .count() as f64;
+            features.holes += (0..*column_height as i32)
+                .filter(|y| !is_filled(x, *y))
+                .count() as f64;
+            let mut depth = 0;
+            for y in (*column_height as i32..height as i32).rev() {
+                if is_filled(x - 1, y) && is_filled(x + 1, y) {
+                    depth += 1;
+                    features.wells += depth as f64;
+                } else {
+                    depth = 0;
+                }
+            }
+        }
+        features
+    }
+}
+
+/// Every position the piece can be brought to rest in from where it is,
+/// through the same moves and rotations (kicks included) a player has,
+/// each with the fewest inputs that get it there. Placements covering the
+/// same cells are only listed once.
+pub fn placements(board: &Matrix<Cell>, piece: &Piece) -> Vec<Placement> {
+    if !board.can_apply(&piece.points) { return vec![] }
+
+    let mut seen = HashSet::from([(piece.orientation, piece.points.clone())]);
+    let mut queue = VecDeque::from([(piece.clone(), vec![])]);
+    let mut landed = HashSet::new();
+    let mut placements = vec![];
+    while let Some((piece, inputs)) = queue.pop_front() {
+        for action in MOVES {
+            let Some(moved) = try_move(board, &piece, action) else { continue };
+            if seen.insert((moved.orientation, moved.points.clone())) {
+                let mut inputs = inputs.clone();
+                inputs.push(action);
+                queue.push_back((moved, inputs));
+            }
+        }
+        if !board.can_apply(&piece.project_down().points) && landed.insert(cells(&piece)) {
+            let mut inputs = inputs;
+            inputs.push(Action::SoftDrop);
+            placements.push(Placement { piece, inputs });
+        }
+    }
+    placements
+}
+
+/// The fewest key presses that bring the piece to rest covering `target`
+/// (its cells, sorted), ending with the soft drop that locks it. Soft drops
+/// in a row count as one press, since the key is held down for them.
+pub fn finesse(board: &Matrix<Cell>, piece: &Piece, target: &[Point]) -> Option<Vec<Action>> {
+    // the pieces reached so far, how, and whether they've locked, searched cheapest
+    // first. Equal costs go to the fewest inputs, then to whichever came first.
+    let mut reached = vec![(piece.clone(), vec![], false)];
+    let mut queue = BinaryHeap::from([Reverse((0, 0, 0))]);
+    let mut done = HashSet::new();
+    while let Some(Reverse((presses, _, i))) = queue.pop() {
+        let (piece, inputs, locked): (Piece, Vec<Action>, bool) = reached[i].clone();
+        if locked { return Some(inputs) }
+        let dropping = inputs.last() == Some(&Action::SoftDrop);
+        if !done.insert((piece.orientation, piece.points.clone(), dropping)) { continue }
+        let moves = MOVES.into_iter().filter_map(|action| Some((try_move(board, &piece, action)?, action, false)));
+        // locking in place is one more drop, free if the key's already held
+        let lock = (cells(&piece) == target && !board.can_apply(&piece.project_down().points))
+            .then(|| (piece.clone(), Action::SoftDrop, true));
+        for (moved, action, locks) in moves.chain(lock) {
+            let held = dropping && action == Action::SoftDrop;
+            let mut inputs = inputs.clone();
+            inputs.push(action);
+            queue.push(Reverse((presses + usize::from(!held), inputs.len(), reached.len())));
+            reached.push((moved, inputs, locks));
+        }
+    }
+    None
+}
+
+/// How many key presses a run of inputs takes, counting the soft drops in
+/// a row as one, the way [`finesse`] does.
+pub fn presses(inputs: &[Action]) -> usize {
+    inputs
+        .iter()
+        .enumerate()
+        .filter(|(i, action)| **action != Action::SoftDrop || *i == 0 || inputs[i - 1] != Action::SoftDrop)
+        .count()
+}
+
+/// Where the AI would put the piece in play, and the keys that get it there
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Hint {
+    // which piece it's for, counting those locked before it
+    pub piece_number: usize,
+    pub piece: Piece,
+    pub keys: Vec<Action>,
+}
+
+impl Hint {
+    pub fn new(game: &Game, weights: &Weights) -> Option<Self> {
+        let placement = best_placement(game, weights)?;
+        let keys = finesse(&game.board, &game.current_piece, &cells(&placement.piece))?;
+        Some(Self { piece_number: game.stats.pieces, piece: placement.piece, keys })
+    }
+
+    pub fn is_for(&self, game: &Game) -> bool {
+        self.piece_number == game.stats.pieces
+    }
+}
+
+/// The placement for the current piece that leaves the best looking board
+pub fn best_placement(game: &Game, weights: &Weights) -> Option<Placement> {
+    best_of(placements(&game.board, &game.current_piece), &game.board, weights)
+}
+
+fn best_of(placements: Vec<Placement>, board: &Matrix<Cell>, weights: &Weights) -> Option<Placement> {
+    placements
+        .into_iter()
+        .map(|placement| {
+            let score = weights.score(&Features::new(board, &placement.piece));
+            (placement, score)
+        })
+        // the first of equally good placements, which is the quickest to reach
+        .reduce(|best, next| if next.1 > best.1 { next } else { best })
+        .map(|(placement, _)| placement)
+}
+
+// As the game would have it: moves are blocked outright, and a
+// rotation takes the first of its kicks that fits.
+fn try_move(board: &Matrix<Cell>, piece: &Piece, action: Action) -> Option<Piece> {
+    let moved = match action {
+        Action::MoveLeft => piece.project_left(),
+        Action::MoveRight => piece.project_right(),
+        Action::SoftDrop => piece.project_down(),
+        Action::RotateClockwise => return first_fit(board, piece.clockwise_rotations()),
+        Action::RotateCounterclockwise => return first_fit(board, piece.counterclockwise_rotations()),
+        Action::Rotate180 => return first_fit(board, piece.rotations_180()),
+        Action::Pause => return None,
+    };
+    board.can_apply(&moved.points).then_some(moved)
+}
+
+fn first_fit(board: &Matrix<Cell>, candidates: Vec<Piece>) -> Option<Piece> {
+    candidates.into_iter().find(|p| board.can_apply(&p.points))
+}
+
+// The cells a piece covers, whichever way round it is
+pub(crate) fn cells(piece: &Piece) -> Vec<Point> {
+    let mut points = piece.points.clone();
+    points.sort();
+    points
+}
+
+/// Plays a game an input at a time. It picks a placement for each new
+/// piece and steers the piece there.
+#[derive(Debug, Default)]
+pub struct Ai {
+    pub weights: Weights,
+    plan: Option<Plan>,
+}
+
+impl Ai {
+    pub fn new(weights: Weights) -> Self {
+        Self { weights, plan: None }
+    }
+
+    /// The next input towards the chosen placement, or None while
+    /// there's no piece to move.
+    pub fn next_action(&mut self, game: &Game) -> Option<Action> {
+        if !is_moving(game) { return None }
+
+        let plan = self.plan.as_mut().filter(|plan| plan.is_for(game));
+        if let Some(action) = plan.and_then(|plan| plan.next_action(game)) {
+            return Some(action)
+        }
+        // a new piece, or the placement can't be reached any more
+        let placement = best_placement(game, &self.weights)?;
+        self.plan.insert(Plan::new(game, placement)).next_action(game)
+    }
+}
+
+// Whether there's a piece in play to steer
+pub(crate) fn is_moving(game: &Game) -> bool {
+    !game.paused && !game.game_over && game.phase == Phase::Falling
+}
+
+// Steers a piece to its placement, searching again from wherever
+// the piece has got to whenever gravity throws it off the path.
+#[derive(Debug)]
+pub(crate) struct Plan {
+    // which piece it's for, counting those locked before it
+    piece_number: usize,
+    target: Vec<Point>,
+    // where the piece should be now, and the inputs from there
+    position: Piece,
+    inputs: VecDeque<Action>,
+}
+
+impl Plan {
+    pub(crate) fn new(game: &Game, placement: Placement) -> Self {
+        Self {
+            piece_number: game.stats.pieces,
+            target: cells(&placement.piece),
+            position: game.current_piece.clone(),
+            inputs: placement.inputs.into(),
+        }
+    }
+
+    pub(crate) fn is_for(&self, game: &Game) -> bool {
+        self.piece_number == game.stats.pieces
+    }
+
+    // None if the piece can't get to the placement from where it is
+    pub(crate) fn next_action(&mut self, game: &Game) -> Option<Action> {
+        if self.position != game.current_piece || self.inputs.is_empty() {
+            let placement = placements(&game.board, &game.current_piece)
+                .into_iter()
+                .find(|placement| cells(&placement.piece) == self.target)?;
+            self.position = game.current_piece.clone();
+            self.inputs = placement.inputs.into();
+        }
+        let action = self.inputs.pop_front()?;
+        if let Some(moved) = try_move(&game.board, &self.position, action) {
+            self.position = moved;
+        }
+        Some(action)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+
+    fn game_with(piece: Piece) -> Game {
+        let mut game = Game::new(Config { seed: Some(1), ..Config::default() });
+        game.board = Matrix::empty();
+        game.board.apply(&piece, 1);
+        game.current_piece = piece;
+        game
+    }
+
+    #[test]
+    fn test_placements() {
+        let board = Matrix::empty();
+        assert_eq!(placements(&board, &Piece::hero(Point::new(4, 18))).len(), 17);
+        assert_eq!(placements(&board, &Piece::smashboy(Point::new(4, 18))).len(), 9);
+        assert_eq!(placements(&board, &Piece::teewee(Point::new(4, 18))).len(), 34);
+    }
+
+    #[test]
+    fn test_placement_inputs_lock_the_piece_there() {
+        let piece = Piece::teewee(Point::new(4, 18));
+        let mut board = Matrix::empty();
+        // an overhang to tuck under
+        (0..4).for_each(|x| { board.set(x, 2, Cell::black(1)); });
+        let placements = placements(&board, &piece);
+        assert!(placements.iter().any(|placement| placement.piece.points.iter().all(|p| p.x < 4 && p.y < 2)));
+
+        for placement in placements {
+            let mut game = game_with(piece.clone());
+            (0..4).for_each(|x| { game.board.set(x, 2, Cell::black(1)); });
+            placement.inputs.iter().for_each(|action| game.on_action(*action));
+            assert_eq!(game.stats.pieces, 1, "{placement:?}");
+            for p in &placement.piece.points {
+                assert_eq!(game.board.get_at(p).unwrap().value, Color::Black, "{placement:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_finesse() {
+        let board = Matrix::empty();
+        let piece = Piece::teewee(Point::new(4, 18));
+        assert_eq!(presses(&[Action::SoftDrop, Action::SoftDrop, Action::MoveLeft, Action::SoftDrop]), 3);
+        // over to the left wall and down
+        let keys = finesse(&board, &piece, &cells(&Piece::teewee(Point::new(0, 0)))).unwrap();
+        assert_eq!(presses(&keys), 5, "{keys:?}");
+        assert_eq!(keys[..4], [Action::MoveLeft; 4]);
+        assert_eq!(keys[4..], [Action::SoftDrop; 19]);
+
+        // a tuck under an overhang takes a drop, a move and another drop
+        let mut board = Matrix::empty();
+        (0..4).for_each(|x| { board.set(x, 2, Cell::black(1)); });
+        let piece = Piece::smashboy(Point::new(4, 18));
+        let keys = finesse(&board, &piece, &cells(&Piece::smashboy(Point::new(0, 0)))).unwrap();
+        assert_eq!(presses(&keys), 6, "{keys:?}");
+        assert_eq!(keys.iter().filter(|action| **action == Action::MoveLeft).count(), 4);
+        assert!(finesse(&board, &piece, &cells(&Piece::smashboy(Point::new(0, 2)))).is_none());
+    }
+
+    #[test]
+    fn test_features() {
+        let mut board = Matrix::empty();
+        // a hole at (1, 0) under the block, and a well two deep at x = 3
+        for (x, y) in [(0, 0), (0, 1), (1, 1), (2, 0), (2, 1), (4, 0), (4, 1)] {
+            board.set(x, y, Cell::black(1));
+        }
+        let piece = Piece::smashboy(Point::new(8, 0));
+        let features = Features::new(&board, &piece);
+        assert_eq!(features.lines_cleared, 0.0);
+        assert_eq!(features.holes, 1.0);
+        assert_eq!(features.landing_height, 0.5);
+        assert_eq!(features.aggregate_height, 2.0 * 6.0);
+        assert_eq!(features.wells, 1.0 + 2.0);
+    }
+
+    #[test]
+    fn test_ai_plays() {
+        let mut game = Game::new(Config { seed: Some(5), ..Config::default() });
+        let mut ai = Ai::default();
+        while game.stats.pieces < 200 && !game.game_over {
+            if let Some(action) = ai.next_action(&game) {
+                game.on_action(action);
+            }
+            game.on_tick();
+        }
+        assert!(!game.game_over);
+        assert!(game.stats.rows_cleared >= 60, "{} lines", game.stats.rows_cleared);
+    }
+}
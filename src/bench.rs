@@ -0,0 +1,169 @@
+//! Plays batches of games with the AI, as fast as they'll go, to compare
+//! sets of [`Weights`] and keep an eye on how quickly the engine runs.
+
+use std::{
+    fmt,
+    ops::Range,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+use serde::Serialize;
+
+use crate::{
+    ai::{self, Weights},
+    config::Config,
+    Game,
+};
+
+/// How one game went
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct GameResult {
+    pub seed: u64,
+    pub score: usize,
+    pub lines: usize,
+    pub pieces: usize,
+}
+
+/// The middle and the average of one stat over every game
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Summary {
+    pub mean: f64,
+    pub median: f64,
+}
+
+impl Summary {
+    pub fn of(values: impl Iterator<Item = usize>) -> Self {
+        let mut values: Vec<usize> = values.collect();
+        if values.is_empty() {
+            return Self { mean: 0.0, median: 0.0 }
+        }
+        values.sort_unstable();
+        let middle = values.len() / 2;
+        let median = if values.len().is_multiple_of(2) {
+            (values[middle - 1] + values[middle]) as f64 / 2.0
+        } else {
+            values[middle] as f64
+        };
+        Self { mean: values.iter().sum::<usize>() as f64 / values.len() as f64, median }
+    }
+}
+
+/// Every game in a batch, and how long they took between them
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Report {
+    pub seeds: Range<u64>,
+    pub threads: usize,
+    pub seconds: f64,
+    pub lines: Summary,
+    pub score: Summary,
+    pub pieces: Summary,
+    pub placements_per_second: f64,
+    pub games: Vec<GameResult>,
+}
+
+impl Report {
+    pub fn new(seeds: Range<u64>, threads: usize, elapsed: Duration, mut games: Vec<GameResult>) -> Self {
+        games.sort_by_key(|game| game.seed);
+        let placements: usize = games.iter().map(|game| game.pieces).sum();
+        let seconds = elapsed.as_secs_f64();
+        Self {
+            seeds,
+            threads,
+            seconds,
+            lines: Summary::of(games.iter().map(|game| game.lines)),
+            score: Summary::of(games.iter().map(|game| game.score)),
+            pieces: Summary::of(games.iter().map(|game| game.pieces)),
+            placements_per_second: if seconds > 0.0 { placements as f64 / seconds } else { 0.0 },
+            games,
+        }
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{} games (seeds {}..{}) on {} threads in {:.2}s",
+            self.games.len(), self.seeds.start, self.seeds.end, self.threads, self.seconds,
+        )?;
+        writeln!(f)?;
+        writeln!(f, "{:<8}{:>12}{:>12}", "", "mean", "median")?;
+        for (name, summary) in [("lines", self.lines), ("score", self.score), ("pieces", self.pieces)] {
+            writeln!(f, "{name:<8}{:>12.1}{:>12.1}", summary.mean, summary.median)?;
+        }
+        writeln!(f)?;
+        write!(f, "{:.0} placements/sec", self.placements_per_second)
+    }
+}
+
+/// Plays a game for each seed, set up from `config`, spread over `threads`.
+pub fn run(config: &Config, weights: &Weights, seeds: Range<u64>, threads: usize) -> Report {
+    let threads = threads.max(1);
+    let next_seed = AtomicU64::new(seeds.start);
+    let games = Mutex::new(vec![]);
+    let start = Instant::now();
+    thread::scope(|scope| {
+        for _ in 0..threads {
+            scope.spawn(|| loop {
+                let seed = next_seed.fetch_add(1, Ordering::Relaxed);
+                if seed >= seeds.end { break }
+                let result = play(config, weights, seed);
+                games.lock().unwrap().push(result);
+            });
+        }
+    });
+    Report::new(seeds, threads, start.elapsed(), games.into_inner().unwrap())
+}
+
+/// Plays one game through, putting each piece where the AI would and
+/// locking it there straight away.
+pub fn play(config: &Config, weights: &Weights, seed: u64) -> GameResult {
+    // pieces lock with no delays, so there's always one to place
+    let mut game = Game::new(Config {
+        seed: Some(seed),
+        line_clear_frames: 0,
+        entry_delay: 0,
+        ..config.clone()
+    });
+    while !game.game_over && !game.should_quit() {
+        let Some(placement) = ai::best_placement(&game, weights) else { break };
+        game.step(&placement.inputs);
+    }
+    GameResult {
+        seed,
+        score: game.stats.score,
+        lines: game.stats.rows_cleared,
+        pieces: game.stats.pieces,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_summary() {
+        assert_eq!(Summary::of([3, 1, 2].into_iter()), Summary { mean: 2.0, median: 2.0 });
+        assert_eq!(Summary::of([4, 1, 10, 2].into_iter()), Summary { mean: 4.25, median: 3.0 });
+        assert_eq!(Summary::of(std::iter::empty()), Summary { mean: 0.0, median: 0.0 });
+    }
+
+    #[test]
+    fn test_run() {
+        let config = Config { board_width: Some(6), board_height: Some(10), ..Config::default() };
+        let report = run(&config, &Weights::default(), 10..16, 3);
+        assert_eq!(report.games.iter().map(|game| game.seed).collect::<Vec<_>>(), (10..16).collect::<Vec<_>>());
+        assert_eq!(report.games[2], play(&config, &Weights::default(), 12));
+        assert!(report.pieces.mean > 0.0);
+
+        let table = report.to_string();
+        assert!(table.starts_with("6 games (seeds 10..16) on 3 threads"), "{table}");
+        let json: serde_json::Value = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["games"].as_array().unwrap().len(), 6);
+        assert_eq!(json["seeds"]["start"], 10);
+    }
+}
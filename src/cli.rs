@@ -0,0 +1,263 @@
+use std::{fmt, path::PathBuf};
+
+use ttrs::{
+    config::{Config, GameMode},
+    game::level::{Level, Theme},
+    settings::Settings,
+};
+
+pub const MIN_BOARD_WIDTH: usize = 6;
+pub const MAX_BOARD_WIDTH: usize = 32;
+pub const MIN_BOARD_HEIGHT: usize = 10;
+pub const MAX_BOARD_HEIGHT: usize = 40;
+pub const MAX_BENCH_GAMES: usize = 1_000_000;
+
+pub const USAGE: &str = "\
+A terminal-based Tetris game
+
+Usage: ttrs [OPTIONS]
+       ttrs bench [OPTIONS]
+
+Options:
+  --mode <a|b>           Game type: a (endless) or b (start over garbage)
+  --level <1-10>         Starting level
+  --pieces <name>        Piece set, e.g. Pentominoes (default: classic tetrominoes)
+  --seed <number>        Seed for the piece sequence and garbage
+  --board <WxH>          Board size, e.g. 12x24
+  --theme <1-10>         Use one color theme throughout
+  --no-menu              Start playing straight away
+  --replay <file>        Watch a recorded game
+  --headless             Play without drawing and print the final score
+  --bot <command>        Have a Tetris Bot Protocol bot play new games
+  -h, --help             Print this help
+
+bench plays games with the AI without drawing them, to compare weights and
+time the engine. --mode, --level, --pieces, --board and --seed (the first
+seed, 0 by default) set up its games, along with:
+  --games <number>       How many games to play (default: 100)
+  --weights <file>       AI weights in TOML, e.g. holes = -8.0 (default: built in)
+  --threads <number>     How many games to play at once (default: one per CPU)
+  --json                 Print the results as JSON rather than a table
+
+Options left out are taken from the settings file. The menu is skipped
+when --mode and --level are both given.";
+
+#[derive(Debug, PartialEq)]
+pub struct CliError(String);
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for CliError {}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct Cli {
+    pub game_mode: Option<GameMode>,
+    // counting from 1
+    pub level: Option<usize>,
+    pub pieces: Option<String>,
+    pub seed: Option<u64>,
+    pub board_size: Option<(usize, usize)>,
+    pub theme: Option<usize>,
+    pub no_menu: bool,
+    pub replay: Option<PathBuf>,
+    pub headless: bool,
+    // the command line of a bot to play instead of the player
+    pub bot: Option<String>,
+    pub bench: Option<Bench>,
+    pub help: bool,
+}
+
+// The options of the bench subcommand
+#[derive(Debug, PartialEq)]
+pub struct Bench {
+    pub games: u64,
+    pub weights: Option<PathBuf>,
+    // None for one per CPU
+    pub threads: Option<usize>,
+    pub json: bool,
+}
+
+impl Default for Bench {
+    fn default() -> Self {
+        Self { games: 100, weights: None, threads: None, json: false }
+    }
+}
+
+impl Cli {
+    // The arguments after the program name. Options that take a
+    // value accept it as the next argument or after an `=`.
+    pub fn parse<I: IntoIterator<Item = String>>(args: I) -> Result<Self, CliError> {
+        let mut cli = Cli::default();
+        let mut args = args.into_iter().peekable();
+        if args.next_if(|arg| arg == "bench").is_some() {
+            cli.bench = Some(Bench::default());
+        }
+        while let Some(arg) = args.next() {
+            let (name, inline) = match arg.split_once('=') {
+                Some((name, value)) => (name.to_string(), Some(value.to_string())),
+                None => (arg, None),
+            };
+            let mut value = || {
+                inline.clone()
+                    .or_else(|| args.next())
+                    .ok_or_else(|| CliError(format!("{name} needs a value")))
+            };
+            match name.as_str() {
+                "--mode" => cli.game_mode = Some(parse_mode(&value()?)?),
+                "--level" => cli.level = Some(parse_in_range("--level", &value()?, 1, Level::level_count())?),
+                "--pieces" => cli.pieces = Some(value()?),
+                "--seed" => {
+                    let seed = value()?;
+                    cli.seed = Some(seed.parse().map_err(|_| CliError(format!("--seed expects a number, found \"{seed}\"")))?)
+                },
+                "--board" => cli.board_size = Some(parse_board_size(&value()?)?),
+                "--theme" => cli.theme = Some(parse_in_range("--theme", &value()?, 1, Theme::count())?),
+                "--replay" => cli.replay = Some(PathBuf::from(value()?)),
+                "--no-menu" => cli.no_menu = true,
+                "--headless" => cli.headless = true,
+                "--bot" => cli.bot = Some(value()?),
+                "--games" | "--weights" | "--threads" | "--json" => {
+                    let Some(bench) = &mut cli.bench else {
+                        return Err(CliError(format!("{name} only goes with bench")))
+                    };
+                    match name.as_str() {
+                        "--games" => bench.games = parse_in_range("--games", &value()?, 1, MAX_BENCH_GAMES)? as u64,
+                        "--weights" => bench.weights = Some(PathBuf::from(value()?)),
+                        "--threads" => bench.threads = Some(parse_in_range("--threads", &value()?, 1, 1024)?),
+                        _ => bench.json = true,
+                    }
+                },
+                "-h" | "--help" => cli.help = true,
+                _ => return Err(CliError(format!("unknown option \"{name}\""))),
+            }
+        }
+        Ok(cli)
+    }
+
+    // Whether there's enough to go on to start without the menu
+    pub fn skips_menu(&self) -> bool {
+        self.no_menu || self.headless || (self.game_mode.is_some() && self.level.is_some())
+    }
+
+    // The choices given on the command line take the place of the saved
+    // ones for this run, without being saved themselves
+    pub fn apply_to_settings(&self, settings: &mut Settings) {
+        if let Some(mode) = self.game_mode { settings.game_mode = mode }
+        if let Some(level) = self.level { settings.level = level }
+        if let Some(pieces) = &self.pieces { settings.piece_set = Some(pieces.clone()) }
+        if let Some(theme) = self.theme { settings.theme = Some(theme) }
+    }
+
+    // Options that aren't settings, only for this run
+    pub fn apply_to_config(&self, config: &mut Config) {
+        if let Some(seed) = self.seed { config.seed = Some(seed) }
+        if let Some((width, height)) = self.board_size {
+            config.board_width = Some(width);
+            config.board_height = Some(height);
+        }
+    }
+}
+
+fn parse_mode(value: &str) -> Result<GameMode, CliError> {
+    match value.to_lowercase().as_str() {
+        "a" | "a-type" => Ok(GameMode::AType),
+        "b" | "b-type" => Ok(GameMode::BType),
+        _ => Err(CliError(format!("--mode expects a or b, found \"{value}\""))),
+    }
+}
+
+fn parse_in_range(name: &str, value: &str, min: usize, max: usize) -> Result<usize, CliError> {
+    match value.parse() {
+        Ok(n) if (min..=max).contains(&n) => Ok(n),
+        _ => Err(CliError(format!("{name} must be between {min} and {max}, found \"{value}\""))),
+    }
+}
+
+fn parse_board_size(value: &str) -> Result<(usize, usize), CliError> {
+    let (width, height) = value
+        .split_once('x')
+        .ok_or_else(|| CliError(format!("--board expects WIDTHxHEIGHT, e.g. 12x24, found \"{value}\"")))?;
+    Ok((
+        parse_in_range("board width", width, MIN_BOARD_WIDTH, MAX_BOARD_WIDTH)?,
+        parse_in_range("board height", height, MIN_BOARD_HEIGHT, MAX_BOARD_HEIGHT)?,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &str) -> Result<Cli, CliError> {
+        Cli::parse(args.split_whitespace().map(String::from))
+    }
+
+    #[test]
+    fn test_parse() {
+        let cli = parse("--mode b --level=7 --seed 42 --board 12x24 --theme 3 --no-menu --bot cold-clear").unwrap();
+        assert_eq!(cli, Cli {
+            game_mode: Some(GameMode::BType),
+            level: Some(7),
+            seed: Some(42),
+            board_size: Some((12, 24)),
+            theme: Some(3),
+            no_menu: true,
+            bot: Some(String::from("cold-clear")),
+            ..Cli::default()
+        });
+        assert!(cli.skips_menu());
+        assert!(!parse("--level 2").unwrap().skips_menu());
+        assert!(parse("--mode a --level 2").unwrap().skips_menu());
+    }
+
+    #[test]
+    fn test_validation() {
+        assert_eq!(
+            parse("--level 11").unwrap_err().to_string(),
+            "--level must be between 1 and 10, found \"11\"",
+        );
+        assert!(parse("--level 0").is_err());
+        assert!(parse("--level").is_err());
+        assert!(parse("--theme 12").is_err());
+        assert!(parse("--mode c").is_err());
+        assert!(parse("--seed -1").is_err());
+        assert!(parse("--board 12").is_err());
+        assert!(parse("--board 100x20").is_err());
+        assert!(parse("--fast").is_err());
+        assert_eq!(parse("--json").unwrap_err().to_string(), "--json only goes with bench");
+        assert!(parse("bench --threads 0").is_err());
+        assert!(parse("--level 2 bench").is_err());
+    }
+
+    #[test]
+    fn test_parse_bench() {
+        let cli = parse("bench --games 20 --seed 100 --threads=4 --weights tuned.toml --json --board 8x16").unwrap();
+        assert_eq!(cli.bench, Some(Bench {
+            games: 20,
+            weights: Some(PathBuf::from("tuned.toml")),
+            threads: Some(4),
+            json: true,
+        }));
+        assert_eq!(cli.seed, Some(100));
+        assert_eq!(cli.board_size, Some((8, 16)));
+        assert_eq!(parse("bench").unwrap().bench, Some(Bench::default()));
+    }
+
+    #[test]
+    fn test_apply() {
+        let cli = parse("--level 3 --pieces Trominoes --seed 5 --board 8x16").unwrap();
+        let mut settings = Settings::default();
+        cli.apply_to_settings(&mut settings);
+        assert_eq!(settings.level, 3);
+        assert_eq!(settings.piece_set.as_deref(), Some("Trominoes"));
+        assert_eq!(settings.game_mode, GameMode::AType);
+
+        let mut config = Config::default();
+        cli.apply_to_config(&mut config);
+        assert_eq!(config.seed, Some(5));
+        assert_eq!((config.board_width, config.board_height), (Some(8), Some(16)));
+    }
+}
@@ -1,23 +1,67 @@
 mod stateful_list;
 mod builder;
+mod controls;
 
-pub use builder::{ConfigBuilder, Section};
+pub use builder::{ConfigBuilder, Section, StartMode};
 
-#[derive(Clone, Copy, Default, PartialEq)]
+use serde::{Deserialize, Serialize};
+use crate::{game::piece_set::PieceSet, settings::Settings};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
 pub enum GameMode {
     #[default]
     AType,
     BType,
 }
 
+#[derive(Clone)]
 pub struct Config {
     pub game_mode: GameMode,
     pub initial_level: usize,
+    // None plays the classic tetrominoes
+    pub piece_set: Option<PieceSet>,
+    // None picks a random one
+    pub seed: Option<u64>,
+    // how many upcoming pieces to show
+    pub preview: usize,
+    pub ghost: bool,
+    // a fixed theme number, None to follow the level
+    pub theme: Option<usize>,
+    // override the piece set's (or the standard) board size
+    pub board_width: Option<usize>,
+    pub board_height: Option<usize>,
+    // ticks for full rows to dissolve, and before the next piece comes in
+    pub line_clear_frames: usize,
+    pub entry_delay: usize,
+    // count finesse faults, which takes a search for each piece placed
+    pub finesse: bool,
+    // finesse training, see Game::training
+    pub training: bool,
 }
 
 impl Config {
-    pub fn builder() -> ConfigBuilder<'static> {
-        ConfigBuilder::default()
+    pub fn builder(settings: Settings) -> ConfigBuilder<'static> {
+        ConfigBuilder::new(settings)
+    }
+
+    // Straight from the settings, without going through the menu. Like
+    // the menu, a piece set that's no longer around means the classic set.
+    pub fn from_settings(settings: &Settings) -> Self {
+        let piece_set = settings.piece_set
+            .as_ref()
+            .and_then(|name| PieceSet::available().into_iter().find(|set| &set.name == name));
+        Config {
+            game_mode: settings.game_mode,
+            initial_level: settings.level - 1,
+            piece_set,
+            preview: settings.preview,
+            ghost: settings.ghost,
+            theme: settings.theme,
+            line_clear_frames: settings.line_clear_frames(),
+            entry_delay: settings.entry_delay(),
+            ..Config::default()
+        }
     }
 }
 
@@ -25,7 +69,18 @@ impl Default for Config {
     fn default() -> Config {
         Config {
             game_mode: GameMode::AType,
-            initial_level: 0
+            initial_level: 0,
+            piece_set: None,
+            seed: None,
+            preview: 1,
+            ghost: false,
+            theme: None,
+            board_width: None,
+            board_height: None,
+            line_clear_frames: 0,
+            entry_delay: 0,
+            finesse: false,
+            training: false,
         }
     }
 }
@@ -1,15 +1,54 @@
 use super::{
+    controls::ControlsEditor,
     stateful_list::StatefulList,
     GameMode,
     Config
 };
+use crate::{
+    game::{piece_set::PieceSet, Game},
+    high_scores::HighScores,
+    saved_game,
+    settings::Settings,
+};
 
 #[derive(Clone, Copy, Default, PartialEq)]
 pub enum Section {
     #[default]
     ChooseGameMode,
     ChooseInitialLevel,
-    StartGame
+    ChoosePieceSet,
+    Resume,
+    StartGame,
+    Controls,
+    HighScores,
+}
+
+// What the start button starts. Left and right go round them.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum StartMode {
+    #[default]
+    Play,
+    // the AI plays while the player watches
+    AiPlays,
+    // finesse training
+    Training,
+    // two players, side by side
+    Versus,
+}
+
+impl StartMode {
+    pub fn all() -> [Self; 4] {
+        [StartMode::Play, StartMode::AiPlays, StartMode::Training, StartMode::Versus]
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            StartMode::Play => "Start",
+            StartMode::AiPlays => "AI Plays",
+            StartMode::Training => "Training",
+            StartMode::Versus => "Versus",
+        }
+    }
 }
 
 pub struct ConfigBuilder<'a> {
@@ -17,80 +56,266 @@ pub struct ConfigBuilder<'a> {
     pub current_section: Section,
     pub sections: Vec<Section>,
     pub game_mode: GameMode,
-    pub level_list: StatefulList<&'a str>
+    pub level_list: StatefulList<&'a str>,
+    pub piece_sets: Vec<PieceSet>,
+    pub piece_set_list: StatefulList<String>,
+    pub high_scores: HighScores,
+    pub showing_high_scores: bool,
+    // the table on show, apart from the game that's been picked
+    pub high_score_mode: GameMode,
+    pub high_score_level: usize,
+    pub controls: ControlsEditor,
+    pub showing_controls: bool,
+    // a game quit with save, offered as Resume
+    pub saved_game: Option<Game>,
+    pub resuming: bool,
+    pub start_mode: StartMode,
+    // what the menu started out with, including
+    // the options it doesn't show
+    settings: Settings,
 }
 
 impl<'a> ConfigBuilder<'a> {
+    // Starts out on the choices in the settings
+    pub fn new(settings: Settings) -> Self {
+        // an unreadable save is treated as no save at all
+        let saved_game = saved_game::load().ok().flatten();
+        let sections = if saved_game.is_some() {
+            vec![Section::Resume]
+        } else {
+            vec![Section::default()]
+        };
+        let piece_sets = PieceSet::available();
+        let piece_set_names = std::iter::once(String::from("Classic"))
+            .chain(piece_sets.iter().map(|set| set.name.clone()))
+            .collect();
+        let mut level_list = StatefulList::with_items(vec![
+            "Level 1",
+            "Level 2",
+            "Level 3",
+            "Level 4",
+            "Level 5",
+            "Level 6",
+            "Level 7",
+            "Level 8",
+            "Level 9",
+            "Level 10",
+        ]);
+        level_list.state.select(Some(settings.level - 1));
+        let mut piece_set_list = StatefulList::with_items(piece_set_names);
+        // a set that's since been removed falls back to the classic pieces
+        let piece_set = settings.piece_set
+            .as_ref()
+            .and_then(|name| piece_sets.iter().position(|set| &set.name == name))
+            .map_or(0, |i| i + 1);
+        piece_set_list.state.select(Some(piece_set));
+
+        ConfigBuilder {
+            is_configured: false,
+            current_section: sections[0],
+            sections,
+            game_mode: settings.game_mode,
+            level_list,
+            piece_sets,
+            piece_set_list,
+            // an unreadable score file shows as an empty table
+            high_scores: HighScores::load().unwrap_or_default(),
+            showing_high_scores: false,
+            high_score_mode: settings.game_mode,
+            high_score_level: settings.level - 1,
+            controls: ControlsEditor::new(settings.keys.clone()),
+            showing_controls: false,
+            saved_game,
+            resuming: false,
+            start_mode: StartMode::default(),
+            settings,
+        }
+    }
+
     pub fn build(&self) -> Config {
         Config {
             game_mode: self.game_mode,
-            initial_level: self.level_list.state.selected().unwrap(),
+            initial_level: self.initial_level(),
+            piece_set: self.selected_piece_set(),
+            seed: None,
+            preview: self.settings.preview,
+            ghost: self.settings.ghost,
+            theme: self.settings.theme,
+            board_width: None,
+            board_height: None,
+            line_clear_frames: self.settings.line_clear_frames(),
+            entry_delay: self.settings.entry_delay(),
+            // only worth the search when someone's playing
+            finesse: matches!(self.start_mode, StartMode::Play | StartMode::Training),
+            training: self.start_mode == StartMode::Training,
+        }
+    }
+
+    // The settings as chosen in the menu, to be remembered for next time
+    pub fn settings(&self) -> Settings {
+        Settings {
+            game_mode: self.game_mode,
+            level: self.initial_level() + 1,
+            piece_set: self.selected_piece_set().map(|set| set.name),
+            keys: self.controls.keys.clone(),
+            ..self.settings.clone()
         }
     }
 
+    // What to save: `saved`, the settings as they are on file, with
+    // whatever was changed in the menu on top. Options given on the
+    // command line only last the one run, unless they're changed here.
+    pub fn changed_settings(&self, saved: &Settings) -> Settings {
+        let (before, after) = (&self.settings, self.settings());
+        let mut settings = saved.clone();
+        if after.game_mode != before.game_mode { settings.game_mode = after.game_mode }
+        if after.level != before.level { settings.level = after.level }
+        if after.piece_set != before.piece_set { settings.piece_set = after.piece_set }
+        if after.keys != before.keys { settings.keys = after.keys }
+        settings
+    }
+
+    // The first entry in the list is the classic tetromino set
+    fn selected_piece_set(&self) -> Option<PieceSet> {
+        let i = self.piece_set_list.state.selected()?;
+        self.piece_sets.get(i.checked_sub(1)?).cloned()
+    }
+
+    // The saved game, if the player chose to pick it back up
+    pub fn resumed_game(&mut self) -> Option<Game> {
+        if !self.resuming { return None }
+        self.saved_game.take()
+    }
+
     pub fn is_configured(&self) -> bool {
         self.is_configured
     }
 
+    pub fn initial_level(&self) -> usize {
+        self.level_list.state.selected().unwrap()
+    }
+
+    // While the high score table is open, up and down switch
+    // between the game modes' tables instead (and left and right
+    // between the levels'), leaving the game's own alone. While the
+    // controls are open they move between its rows.
     pub fn previous_section(&mut self) {
+        if self.showing_controls {
+            self.controls.previous();
+            return
+        }
+        if self.showing_high_scores {
+            self.high_score_mode = GameMode::AType;
+            return
+        }
         match self.current_section {
-            Section::StartGame => self.current_section = Section::ChooseInitialLevel,
+            Section::HighScores => self.current_section = Section::Controls,
+            Section::Controls => self.current_section = Section::StartGame,
+            Section::StartGame if self.saved_game.is_some() => self.current_section = Section::Resume,
+            Section::StartGame => self.current_section = Section::ChoosePieceSet,
+            Section::Resume => self.current_section = Section::ChoosePieceSet,
+            Section::ChoosePieceSet => self.current_section = Section::ChooseInitialLevel,
             Section::ChooseInitialLevel => self.current_section = Section::ChooseGameMode,
             _ => {}
         }
     }
 
     pub fn next_section(&mut self) {
+        if self.showing_controls {
+            self.controls.next();
+            return
+        }
+        if self.showing_high_scores {
+            self.high_score_mode = GameMode::BType;
+            return
+        }
         match self.current_section {
             Section::ChooseGameMode => self.current_section = Section::ChooseInitialLevel,
-            Section::ChooseInitialLevel => self.current_section = Section::StartGame,
+            Section::ChooseInitialLevel => self.current_section = Section::ChoosePieceSet,
+            Section::ChoosePieceSet if self.saved_game.is_some() => self.current_section = Section::Resume,
+            Section::ChoosePieceSet => self.current_section = Section::StartGame,
+            Section::Resume => self.current_section = Section::StartGame,
+            Section::StartGame => self.current_section = Section::Controls,
+            Section::Controls => self.current_section = Section::HighScores,
             _ => {}
         }
     }
 
     pub fn on_left(&mut self) {
+        if self.showing_controls {
+            self.controls.cycle_preset(false);
+            return
+        }
+        if self.showing_high_scores {
+            let levels = self.level_list.items.len();
+            self.high_score_level = (self.high_score_level + levels - 1) % levels;
+            return
+        }
         match self.current_section {
             Section::ChooseGameMode => self.game_mode = GameMode::AType,
             Section::ChooseInitialLevel => self.level_list.previous(),
+            Section::ChoosePieceSet => self.piece_set_list.previous(),
+            Section::StartGame => self.cycle_start(false),
             _ => {}
         }
     }
 
     pub fn on_right(&mut self) {
+        if self.showing_controls {
+            self.controls.cycle_preset(true);
+            return
+        }
+        if self.showing_high_scores {
+            self.high_score_level = (self.high_score_level + 1) % self.level_list.items.len();
+            return
+        }
         match self.current_section {
             Section::ChooseGameMode => self.game_mode = GameMode::BType,
             Section::ChooseInitialLevel => self.level_list.next(),
+            Section::ChoosePieceSet => self.piece_set_list.next(),
+            Section::StartGame => self.cycle_start(true),
             _ => {}
         }
     }
 
+    fn cycle_start(&mut self, forward: bool) {
+        let modes = StartMode::all();
+        let current = modes.iter().position(|mode| *mode == self.start_mode).unwrap_or(0);
+        let step = if forward { 1 } else { modes.len() - 1 };
+        self.start_mode = modes[(current + step) % modes.len()];
+    }
+
     pub fn configured(&mut self) {
-        if self.current_section == Section::StartGame {
-            self.is_configured = true;
+        if self.showing_controls {
+            self.controls.select();
+            return
+        }
+        match self.current_section {
+            Section::StartGame => self.is_configured = true,
+            Section::Resume => {
+                self.resuming = true;
+                self.is_configured = true;
+            },
+            Section::Controls => self.showing_controls = true,
+            // the table opens on the game that's been picked
+            Section::HighScores => {
+                self.high_score_mode = self.game_mode;
+                self.high_score_level = self.initial_level();
+                self.showing_high_scores = !self.showing_high_scores;
+            },
+            _ => {}
         }
     }
+
+    // Closes the high score table or the controls
+    pub fn back(&mut self) {
+        self.showing_high_scores = false;
+        self.showing_controls = false;
+    }
 }
 
 impl<'a> Default for ConfigBuilder<'a> {
     fn default() -> Self {
-        let sections = vec![Section::default()];
-        ConfigBuilder {
-            is_configured: false,
-            current_section: sections[0],
-            sections,
-            game_mode: GameMode::default(),
-            level_list: StatefulList::with_items(vec![
-                "Level 1",
-                "Level 2",
-                "Level 3",
-                "Level 4",
-                "Level 5",
-                "Level 6",
-                "Level 7",
-                "Level 8",
-                "Level 9",
-                "Level 10",
-            ])
-        }
+        Self::new(Settings::default())
     }
 }
@@ -0,0 +1,108 @@
+use termion::event::Key;
+
+use crate::{
+    game::action::Action,
+    input::{KeyBindings, Preset},
+};
+
+// The controls screen: a row to pick a preset from, then a row
+// per action that waits for a new key when selected.
+pub struct ControlsEditor {
+    pub keys: KeyBindings,
+    // 0 is the preset row, then the actions in order
+    pub selected: usize,
+    // the action waiting for its new key
+    pub waiting: Option<Action>,
+    // why the last key pressed couldn't be used
+    pub message: Option<String>,
+}
+
+impl ControlsEditor {
+    pub fn new(keys: KeyBindings) -> Self {
+        Self { keys, selected: 0, waiting: None, message: None }
+    }
+
+    pub fn rows() -> usize {
+        Action::all().len() + 1
+    }
+
+    pub fn selected_action(&self) -> Option<Action> {
+        Action::all().get(self.selected.checked_sub(1)?).copied()
+    }
+
+    pub fn preset(&self) -> Option<Preset> {
+        Preset::matching(&self.keys)
+    }
+
+    pub fn previous(&mut self) {
+        self.selected = self.selected.checked_sub(1).unwrap_or(Self::rows() - 1);
+    }
+
+    pub fn next(&mut self) {
+        self.selected = (self.selected + 1) % Self::rows();
+    }
+
+    // Left and right on the preset row step through the presets. Bindings
+    // that don't match one go to the first preset, or the last going left.
+    pub fn cycle_preset(&mut self, forward: bool) {
+        if self.selected != 0 { return }
+        let presets = Preset::all();
+        let i = match (self.preset(), forward) {
+            (Some(preset), true) => (presets.iter().position(|p| *p == preset).unwrap() + 1) % presets.len(),
+            (Some(preset), false) => presets.iter().position(|p| *p == preset).unwrap().checked_sub(1).unwrap_or(presets.len() - 1),
+            (None, true) => 0,
+            (None, false) => presets.len() - 1,
+        };
+        self.keys = presets[i].bindings();
+        self.message = None;
+    }
+
+    // On an action's row, starts waiting for its key
+    pub fn select(&mut self) {
+        self.waiting = self.selected_action();
+        self.message = None;
+    }
+
+    // The key pressed while waiting. Esc leaves the binding as it was.
+    pub fn press(&mut self, key: Key) {
+        let Some(action) = self.waiting.take() else { return };
+        if key == Key::Esc { return }
+        self.message = self.keys.bind(action, key).err();
+    }
+
+    pub fn is_waiting(&self) -> bool {
+        self.waiting.is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rebinding() {
+        let mut editor = ControlsEditor::new(KeyBindings::default());
+        editor.cycle_preset(true);
+        assert_eq!(editor.preset(), Some(Preset::Arrows));
+        editor.cycle_preset(false);
+        editor.cycle_preset(false);
+        assert_eq!(editor.preset(), Some(Preset::Guideline));
+
+        editor.next();
+        editor.select();
+        assert_eq!(editor.waiting, Some(Action::MoveLeft));
+        editor.press(Key::Char('q'));
+        assert!(editor.message.is_some());
+        assert_eq!(editor.keys.keys_for(Action::MoveLeft), [Key::Left]);
+
+        editor.select();
+        editor.press(Key::Char('j'));
+        assert!(!editor.is_waiting());
+        assert_eq!(editor.keys.action_for(Key::Char('j')), Some(Action::MoveLeft));
+        assert_eq!(editor.preset(), None);
+        // the preset row isn't an action
+        editor.previous();
+        editor.select();
+        assert!(!editor.is_waiting());
+    }
+}
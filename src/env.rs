@@ -0,0 +1,310 @@
+//! A gym-style environment for training agents on the game.
+//!
+//! An [`Env`] is reset onto a seed and stepped with an action at a time,
+//! an index into its [`ActionSpace`], returning what the agent now sees,
+//! the points the step scored and whether the game is over. [`VecEnv`]
+//! steps many of them at once.
+
+use std::thread;
+
+use crate::{
+    ai::{self, Placement},
+    config::Config,
+    game::{
+        action::Action,
+        matrix::{Color, Matrix},
+        piece::Piece,
+        Game,
+    },
+    GameEvent,
+};
+
+/// The inputs of [`ActionSpace::Inputs`], one of which goes in each tick.
+/// The first is to leave the piece to fall.
+pub const INPUTS: [Option<Action>; 7] = [
+    None,
+    Some(Action::MoveLeft),
+    Some(Action::MoveRight),
+    Some(Action::SoftDrop),
+    Some(Action::RotateClockwise),
+    Some(Action::RotateCounterclockwise),
+    Some(Action::Rotate180),
+];
+
+/// How an agent's actions are read.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ActionSpace {
+    /// One input a tick, an index into [`INPUTS`]
+    Inputs,
+    /// Where to put the piece, an index into [`Env::placements`]. The piece
+    /// goes straight there and locks, and the next one comes in.
+    Placements,
+}
+
+/// What an agent gets to see of the game after each step.
+#[derive(Clone, PartialEq)]
+pub struct Observation {
+    /// The settled cells, bottom row first
+    pub grid: Matrix<bool>,
+    pub piece: Piece,
+    /// The pieces to come, the next one first
+    pub next: Vec<Piece>,
+    pub score: usize,
+    pub lines: usize,
+    pub level: usize,
+    pub pieces: usize,
+}
+
+impl Observation {
+    pub fn new(game: &Game) -> Self {
+        let rows = game.board.rows
+            .iter()
+            .map(|row| row.iter().map(|cell| cell.value == Color::Black).collect())
+            .collect();
+        Self {
+            grid: Matrix { rows },
+            piece: game.current_piece.clone(),
+            next: std::iter::once(&game.next_piece).chain(&game.upcoming).cloned().collect(),
+            score: game.stats.score,
+            lines: game.stats.rows_cleared,
+            level: game.level.number,
+            pieces: game.stats.pieces,
+        }
+    }
+
+    /// Flattened for a network to take in: the settled cells, then the
+    /// piece's cells and the next piece's (where it'll come in) as grids of
+    /// their own, each a row at a time from the bottom, then the stats.
+    pub fn to_vec(&self) -> Vec<f32> {
+        let (width, height) = (self.grid.width(), self.grid.height());
+        let mut planes = vec![Matrix::new(width, height, false), Matrix::new(width, height, false)];
+        for (plane, piece) in planes.iter_mut().zip(std::iter::once(&self.piece).chain(self.next.first())) {
+            piece.points.iter().for_each(|p| { plane.set_at(p, true); });
+        }
+        std::iter::once(&self.grid)
+            .chain(&planes)
+            .flat_map(|plane| plane.rows.iter().flatten())
+            .map(|filled| if *filled { 1.0 } else { 0.0 })
+            .chain([self.score, self.lines, self.level, self.pieces].map(|stat| stat as f32))
+            .collect()
+    }
+}
+
+/// One game for an agent to play, over and over.
+pub struct Env {
+    config: Config,
+    space: ActionSpace,
+    game: Game,
+    // where the piece can go, for the placement action space
+    placements: Vec<Placement>,
+}
+
+impl Env {
+    /// Games are set up from `config`, other than the seed, which comes
+    /// from [`Env::reset`]. Placements lock without delays, so there's
+    /// always one to make.
+    pub fn new(config: Config, space: ActionSpace) -> Self {
+        let config = match space {
+            ActionSpace::Inputs => config,
+            ActionSpace::Placements => Config { line_clear_frames: 0, entry_delay: 0, ..config },
+        };
+        let game = Game::new(Config { seed: Some(0), ..config.clone() });
+        let mut env = Self { config, space, game, placements: vec![] };
+        env.update_placements();
+        env
+    }
+
+    /// Starts a new game dealt from `seed`.
+    pub fn reset(&mut self, seed: u64) -> Observation {
+        self.game = Game::new(Config { seed: Some(seed), ..self.config.clone() });
+        self.update_placements();
+        Observation::new(&self.game)
+    }
+
+    /// Plays `action`, and returns what came of it: the game as it is now,
+    /// the points scored and whether the game has ended.
+    ///
+    /// # Panics
+    ///
+    /// If `action` isn't one of the [`Env::action_count`] there are.
+    pub fn step(&mut self, action: usize) -> (Observation, f64, bool) {
+        let inputs = match self.space {
+            ActionSpace::Inputs => INPUTS[action].into_iter().collect(),
+            ActionSpace::Placements => self.placements
+                .get(action)
+                .unwrap_or_else(|| panic!("there's no placement {action} of {}", self.placements.len()))
+                .inputs
+                .clone(),
+        };
+        let reward = self.game
+            .step(&inputs)
+            .iter()
+            .map(|event| match event {
+                GameEvent::Scored { points, .. } => *points as f64,
+                _ => 0.0,
+            })
+            .sum();
+        self.update_placements();
+        (Observation::new(&self.game), reward, self.is_done())
+    }
+
+    /// How many actions there are to choose from right now
+    pub fn action_count(&self) -> usize {
+        match self.space {
+            ActionSpace::Inputs => INPUTS.len(),
+            ActionSpace::Placements => self.placements.len(),
+        }
+    }
+
+    /// Where the piece can be put, in the placement action space
+    pub fn placements(&self) -> &[Placement] {
+        &self.placements
+    }
+
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+
+    // Topped out, or through the last level
+    fn is_done(&self) -> bool {
+        self.game.game_over || self.game.should_quit()
+    }
+
+    fn update_placements(&mut self) {
+        self.placements = match self.space {
+            ActionSpace::Placements if !self.is_done() => ai::placements(&self.game.board, &self.game.current_piece),
+            _ => vec![],
+        };
+    }
+}
+
+/// Many games stepped side by side, split up between threads. A game that
+/// ends starts over straight away on the next seed, so each step's
+/// observations are always ones to act on.
+pub struct VecEnv {
+    pub envs: Vec<Env>,
+    next_seed: u64,
+    threads: usize,
+}
+
+impl VecEnv {
+    pub fn new(config: Config, space: ActionSpace, count: usize) -> Self {
+        let envs = (0..count).map(|_| Env::new(config.clone(), space)).collect();
+        let threads = thread::available_parallelism().map_or(1, |n| n.get());
+        Self { envs, next_seed: 0, threads }
+    }
+
+    /// Starts every game over, on seeds counting up from `seed`.
+    pub fn reset(&mut self, seed: u64) -> Vec<Observation> {
+        self.next_seed = seed;
+        (0..self.envs.len()).map(|i| self.reset_env(i)).collect()
+    }
+
+    /// Plays an action in each game. A game that ends reports its last
+    /// reward and `true`, with the observation of the game that replaces it.
+    pub fn step(&mut self, actions: &[usize]) -> Vec<(Observation, f64, bool)> {
+        assert_eq!(actions.len(), self.envs.len(), "one action for each game");
+        let chunk = self.envs.len().div_ceil(self.threads).max(1);
+        let mut results: Vec<_> = thread::scope(|scope| {
+            let handles: Vec<_> = self.envs
+                .chunks_mut(chunk)
+                .zip(actions.chunks(chunk))
+                .map(|(envs, actions)| scope.spawn(move || {
+                    envs.iter_mut()
+                        .zip(actions)
+                        .map(|(env, action)| env.step(*action))
+                        .collect::<Vec<_>>()
+                }))
+                .collect();
+            handles.into_iter().flat_map(|handle| handle.join().unwrap()).collect()
+        });
+        // in order, so the seeds go to the same games however it's split up
+        for (i, (observation, _, done)) in results.iter_mut().enumerate() {
+            if *done {
+                *observation = self.reset_env(i);
+            }
+        }
+        results
+    }
+
+    fn reset_env(&mut self, i: usize) -> Observation {
+        let seed = self.next_seed;
+        self.next_seed += 1;
+        self.envs[i].reset(seed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{Rng, SeedableRng};
+    use rand_pcg::Pcg64;
+
+    fn small_board() -> Config {
+        Config { board_width: Some(6), board_height: Some(8), ..Config::default() }
+    }
+
+    #[test]
+    fn test_observation() {
+        let mut env = Env::new(Config::default(), ActionSpace::Placements);
+        let observation = env.reset(7);
+        assert!(observation.grid.rows.iter().flatten().all(|filled| !filled));
+        assert_eq!(observation.next.len(), 1);
+        assert_eq!(observation.to_vec().len(), 3 * 200 + 4);
+        assert_eq!(observation.to_vec().iter().filter(|value| **value == 1.0).count(), 8 + 1);
+        assert!(env.reset(7) == observation);
+    }
+
+    #[test]
+    fn test_placement_step() {
+        let mut env = Env::new(Config::default(), ActionSpace::Placements);
+        env.reset(7);
+        let placement = env.placements()[0].piece.clone();
+        let (observation, reward, done) = env.step(0);
+        assert_eq!((reward, done), (0.0, false));
+        assert_eq!(observation.pieces, 1);
+        assert!(placement.points.iter().all(|p| observation.grid.get_at(p) == Some(true)));
+        assert!(env.action_count() > 0);
+    }
+
+    #[test]
+    fn test_random_placements() {
+        let mut rng = Pcg64::seed_from_u64(1);
+        let mut env = VecEnv::new(small_board(), ActionSpace::Placements, 16);
+        env.reset(0);
+        let (mut episodes, mut points) = (0, 0.0);
+        while episodes < 1000 {
+            let actions: Vec<_> = env.envs.iter().map(|env| rng.gen_range(0..env.action_count())).collect();
+            for (observation, reward, done) in env.step(&actions) {
+                points += reward;
+                if done {
+                    episodes += 1;
+                    assert_eq!(observation.pieces, 0);
+                }
+            }
+        }
+        assert!(points > 0.0);
+    }
+
+    #[test]
+    fn test_random_inputs() {
+        let mut rng = Pcg64::seed_from_u64(1);
+        let mut env = VecEnv::new(small_board(), ActionSpace::Inputs, 16);
+        env.reset(0);
+        let mut episodes = 0;
+        while episodes < 2000 {
+            let actions: Vec<_> = (0..env.envs.len()).map(|_| rng.gen_range(0..INPUTS.len())).collect();
+            episodes += env.step(&actions).iter().filter(|(_, _, done)| *done).count();
+        }
+    }
+
+    #[test]
+    fn test_vec_env_is_deterministic() {
+        let play = || {
+            let mut env = VecEnv::new(small_board(), ActionSpace::Placements, 4);
+            env.reset(10);
+            (0..50).map(|_| env.step(&[0; 4]).into_iter().map(|(_, reward, done)| (reward, done)).collect::<Vec<_>>()).collect::<Vec<_>>()
+        };
+        assert_eq!(play(), play());
+    }
+}
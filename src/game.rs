@@ -1,49 +1,129 @@
+pub mod action;
+pub mod event;
+pub mod garbage;
 pub mod matrix;
 pub mod piece;
 pub mod level;
 pub mod scoring;
+pub mod piece_set;
 mod rotate;
 
-use matrix::{Matrix, Cell};
+use rand::{thread_rng, Rng, SeedableRng};
+use rand_pcg::Pcg64;
+use serde::{Deserialize, Serialize};
+use action::Action;
+use event::GameEvent;
+use garbage::{Clear, Garbage, GarbageQueue};
+use matrix::{Matrix, Cell, Color};
 use piece::{Piece, Point};
-use level::Level;
-use scoring::RowsCleared;
-use crate::config::{Config, GameMode};
-
+use piece_set::PieceSet;
+use level::{Level, Theme};
+use crate::{
+    ai::{self, Hint, Weights},
+    config::{Config, GameMode},
+};
+
+#[derive(Default, Serialize, Deserialize)]
 pub struct Stats {
     pub score: usize,
     pub rows_cleared: usize,
+    // pieces locked onto the board
+    #[serde(default)]
+    pub pieces: usize,
+    // pieces placed with more key presses than they needed
+    #[serde(default)]
+    pub finesse_faults: usize,
 }
 
 impl Stats {
     pub fn new() -> Self {
-        Self { score: 0, rows_cleared: 0 }
+        Self { score: 0, rows_cleared: 0, pieces: 0, finesse_faults: 0 }
     }
 
-    pub fn update(&mut self, score: usize, rows: &RowsCleared) {
+    pub fn update(&mut self, score: usize, rows: usize) {
         self.score += score;
-        self.record_rows_cleared(rows);
+        self.rows_cleared += rows;
     }
+}
 
-    fn record_rows_cleared(&mut self, rows: &RowsCleared) {
-        self.rows_cleared += match rows {
-            RowsCleared::Zero => 0,
-            RowsCleared::One => 1,
-            RowsCleared::Two => 2,
-            RowsCleared::Three => 3,
-            RowsCleared::Four => 4,
-        }
-    }
+// What the game is doing between one piece and the next
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum Phase {
+    // a piece is in play
+    #[default]
+    Falling,
+    // full rows dissolve before they're taken out
+    Clearing { rows: Vec<usize>, frames_left: usize },
+    // the pause before the next piece comes in (ARE)
+    Entry { frames_left: usize },
 }
 
+/// A game in progress, from the first piece to the game over. Input goes
+/// in through [`Game::on_action`] and time through [`Game::on_tick`];
+/// the board, pieces and stats can be read straight off the fields.
+#[derive(Serialize, Deserialize)]
 pub struct Game {
     pub board: Matrix<Cell>,
     pub current_piece: Piece,
     pub next_piece: Piece,
+    // the pieces after next_piece, when more than one is previewed
+    pub upcoming: Vec<Piece>,
+    pub preview: usize,
+    pub show_ghost: bool,
     pub stats: Stats,
     pub level: Level,
     pub levels: Vec<Level>,
+    pub piece_set: Option<PieceSet>,
+    // kept for the high score table
+    pub game_mode: GameMode,
+    pub initial_level: usize,
+    // every random draw comes from here, so a saved game deals the same
+    // pieces once it's resumed and a replay can start over from the seed
+    pub seed: u64,
+    rng: Pcg64,
+    // in ticks; saves from before there were delays have none
+    #[serde(default)]
+    pub phase: Phase,
+    #[serde(default)]
+    pub line_clear_frames: usize,
+    #[serde(default)]
+    pub entry_delay: usize,
+    // whether finesse faults are counted
+    #[serde(default)]
+    pub finesse: bool,
+    // the current piece as it came in, and the keys pressed for it since,
+    // to check its finesse once it's placed
+    #[serde(default)]
+    spawned: Option<Piece>,
+    #[serde(default)]
+    inputs: Vec<Action>,
+    // finesse training: each piece has to go where the AI would put it,
+    // with no more presses than it takes, or it's sent back up to try again
+    #[serde(default)]
+    pub training: bool,
+    #[serde(default)]
+    pub target: Option<Hint>,
+    // garbage on its way, to come up from the bottom once a piece
+    // locks without clearing anything
+    #[serde(default)]
+    pub garbage: GarbageQueue,
+    // whether the piece last moved by turning, for spotting spins
+    #[serde(default)]
+    rotated: bool,
+    // the pieces in a row that have cleared lines, up to the last one
+    #[serde(default)]
+    streak: usize,
+    // whether the last clear was a tetris or a spin
+    #[serde(default)]
+    back_to_back: bool,
+    // collects what happens during a step, and is None outside of one
+    #[serde(skip)]
+    events: Option<Vec<GameEvent>>,
+    #[serde(skip)]
     pub wants_to_quit: bool,
+    // got through the last level, which ends the game like topping out
+    #[serde(default)]
+    pub completed: bool,
     pub paused: bool,
     pub game_over: bool,
 }
@@ -55,26 +135,67 @@ impl Default for Game {
 }
 
 impl Game {
+    /// Deals the first pieces. The same config and seed always deal the same game.
     pub fn new(config: Config) -> Self {
+        let mut piece_set = config.piece_set;
+        let width = config.board_width
+            .or(piece_set.as_ref().and_then(|set| set.board_width))
+            .unwrap_or(matrix::WIDTH);
+        let height = config.board_height
+            .or(piece_set.as_ref().and_then(|set| set.board_height))
+            .unwrap_or(matrix::HEIGHT);
+        let seed = config.seed.unwrap_or_else(|| thread_rng().gen());
+        let mut rng = Pcg64::seed_from_u64(seed);
         let board = match config.game_mode {
-            GameMode::AType => Matrix::empty(),
-            GameMode::BType => Matrix::random_partial_fill()
+            GameMode::AType => Matrix::empty_sized(width, height),
+            GameMode::BType => Matrix::random_partial_fill(width, height, &mut rng)
         };
-        let origin = Point::new(4, 18);
         let mut levels: Vec<Level> = Level::all()[config.initial_level..].to_vec();
+        if let Some(theme) = config.theme {
+            levels.iter_mut().for_each(|level| level.theme = Theme::number(theme));
+        }
         let level = levels.remove(0);
+        let current_piece = spawn_piece(&mut piece_set, &board, &mut rng);
+        let next_piece = spawn_piece(&mut piece_set, &board, &mut rng);
+        let upcoming = (1..config.preview)
+            .map(|_| spawn_piece(&mut piece_set, &board, &mut rng))
+            .collect();
 
-        Self {
+        let mut game = Self {
             board,
             level,
             levels,
-            current_piece: Piece::random(origin),
-            next_piece: Piece::random(origin),
+            current_piece,
+            next_piece,
+            upcoming,
+            preview: config.preview,
+            show_ghost: config.ghost,
+            piece_set,
+            game_mode: config.game_mode,
+            initial_level: config.initial_level,
+            seed,
+            rng,
+            phase: Phase::Falling,
+            line_clear_frames: config.line_clear_frames,
+            entry_delay: config.entry_delay,
+            finesse: config.finesse,
+            spawned: None,
+            inputs: vec![],
+            training: config.training,
+            target: None,
+            garbage: GarbageQueue::new(seed),
+            rotated: false,
+            streak: 0,
+            back_to_back: false,
             stats: Stats::new(),
+            events: None,
             wants_to_quit: false,
+            completed: false,
             paused: false,
             game_over: false,
-        }
+        };
+        game.piece_spawned();
+        game
     }
 
     pub fn quit(&mut self) {
@@ -100,18 +221,65 @@ impl Game {
     }
 
     pub fn on_rotate_clockwise(&mut self) {
-        self.handle_movement(Piece::project_clockwise_rotation);
+        self.handle_rotation(Piece::clockwise_rotations);
     }
 
     pub fn on_rotate_counterclockwise(&mut self) {
-        self.handle_movement(Piece::project_counterclockwise_rotation);
+        self.handle_rotation(Piece::counterclockwise_rotations);
     }
 
-    pub fn on_tick(&mut self) {
-        if self.is_stopped() { return }
+    // Turns straight to the opposite orientation, so the piece
+    // never has to fit in the quarter-turn position on the way.
+    pub fn on_rotate_180(&mut self) {
+        self.handle_rotation(Piece::rotations_180);
+    }
+
+    /// Applies one of the player's actions. Ones that don't fit are ignored.
+    pub fn on_action(&mut self, action: Action) {
+        if action != Action::Pause && !self.is_stopped() {
+            self.inputs.push(action);
+        }
+        match action {
+            Action::MoveLeft => self.on_left(),
+            Action::MoveRight => self.on_right(),
+            Action::SoftDrop => self.on_down(),
+            Action::RotateClockwise => self.on_rotate_clockwise(),
+            Action::RotateCounterclockwise => self.on_rotate_counterclockwise(),
+            Action::Rotate180 => self.on_rotate_180(),
+            Action::Pause => self.toggle_pause(),
+        }
+    }
+
+    /// Applies the actions pressed during a tick, in order, then moves time
+    /// on by the tick, and returns what happened as a result.
+    pub fn step(&mut self, inputs: &[Action]) -> Vec<GameEvent> {
+        self.events = Some(vec![]);
+        for action in inputs {
+            self.on_action(*action);
+        }
+        self.on_tick();
+        self.events.take().unwrap_or_default()
+    }
 
-        if self.level.tick().is_some() {
-            self.on_down();
+    fn emit(&mut self, event: GameEvent) {
+        if let Some(events) = &mut self.events {
+            events.push(event);
+        }
+    }
+
+    /// Moves time on by a tick, a hundredth of a second of play.
+    pub fn on_tick(&mut self) {
+        if self.paused || self.game_over { return }
+
+        match &mut self.phase {
+            Phase::Falling => if self.level.tick().is_some() {
+                self.on_down();
+            },
+            Phase::Clearing { frames_left, .. } | Phase::Entry { frames_left } if *frames_left > 1 => {
+                *frames_left -= 1;
+            },
+            Phase::Clearing { .. } => self.clear_rows(),
+            Phase::Entry { .. } => self.bring_in_next_piece(),
         }
     }
 
@@ -120,45 +288,201 @@ impl Game {
         self.paused = !self.paused;
     }
 
+    // Pieces only move while one is falling
     fn is_stopped(&self) -> bool {
-        self.paused || self.game_over
+        self.paused || self.game_over || self.phase != Phase::Falling
     }
 
     fn handle_movement<F>(&mut self, attempt_move: F) -> Option<()>
-        where F: Fn(&Piece) -> Option<Piece>
+        where F: Fn(&Piece) -> Piece
+    {
+        if self.is_stopped() { return None }
+
+        let projection = attempt_move(&self.current_piece);
+        self.move_to(projection)?;
+        self.rotated = false;
+        Some(())
+    }
+
+    // Rotations may offer several candidate placements (kicks);
+    // the first one that fits on the board wins.
+    fn handle_rotation<F>(&mut self, attempt_rotation: F) -> Option<()>
+        where F: Fn(&Piece) -> Vec<Piece>
     {
         if self.is_stopped() { return None }
 
-        let projection = attempt_move(&self.current_piece)?;
+        let projection = attempt_rotation(&self.current_piece)
+            .into_iter()
+            .find(|p| self.board.can_apply(&p.points))?;
+        self.move_to(projection)?;
+        self.rotated = true;
+        Some(())
+    }
+
+    fn move_to(&mut self, projection: Piece) -> Option<()> {
         let color = self.piece_color(&projection);
 
-        if self.board.apply(projection, color).is_some() {
+        if self.board.apply(&projection, color).is_some() {
             self.current_piece = projection;
+            self.emit(GameEvent::PieceMoved { piece: self.current_piece.clone() });
             Some(())
         } else {
             None
         }
     }
 
+    // Where the current piece would land if it kept falling
+    pub fn ghost_piece(&self) -> Piece {
+        let mut ghost = self.current_piece.clone();
+        loop {
+            let below = ghost.project_down();
+            if !self.board.can_apply(&below.points) { return ghost }
+            ghost = below;
+        }
+    }
+
     pub fn piece_color(&self, piece: &Piece) -> u8 {
         self.level.theme.piece_color(piece)
     }
 
     fn piece_placed(&mut self) {
-        if self.is_stopped() { return }
+        if self.is_stopped() || !self.check_finesse() { return }
 
+        self.rotated &= self.is_immobile();
         self.board.settle(
             &self.current_piece.points,
             self.piece_color(&self.current_piece)
         );
+        self.stats.pieces += 1;
+        self.emit(GameEvent::PieceLocked { piece: self.current_piece.clone() });
+        let rows = self.board.full_rows();
+        if rows.is_empty() || self.line_clear_frames == 0 {
+            self.clear_rows();
+        } else {
+            self.phase = Phase::Clearing { rows, frames_left: self.line_clear_frames };
+        }
+    }
+
+    // Measures the keys pressed for the piece against the fewest that would
+    // have put it there, and says whether it can lock. In training, a piece
+    // that's off target or took extra presses goes back up instead.
+    fn check_finesse(&mut self) -> bool {
+        let Some(spawned) = self.spawned.clone() else { return true };
+        let placed = ai::cells(&self.current_piece);
+        let presses = ai::presses(&self.inputs);
+        // a single press is as few as any piece takes
+        let fewest = if presses > 1 {
+            ai::finesse(&self.board, &spawned, &placed).map(|keys| ai::presses(&keys))
+        } else {
+            None
+        };
+        let fault = fewest.is_some_and(|fewest| presses > fewest);
+        if let (Some(fewest), true) = (fewest, fault) {
+            self.stats.finesse_faults += 1;
+            self.emit(GameEvent::FinesseFault { presses, fewest });
+        }
+        let on_target = self.target.as_ref().is_none_or(|target| ai::cells(&target.piece) == placed);
+        if !self.training || (on_target && !fault) {
+            return true
+        }
+        self.inputs.clear();
+        self.move_to(spawned.clone());
+        self.emit(GameEvent::PieceRetried { piece: spawned });
+        false
+    }
+
+    // Whether the piece is wedged in, with no way out sideways or up
+    fn is_immobile(&self) -> bool {
+        [(-1, 0), (1, 0), (0, 1)]
+            .iter()
+            .all(|(dx, dy)| !self.board.can_apply(&self.current_piece.translate(*dx, *dy).points))
+    }
+
+    // Takes out the full rows, if any, and scores them
+    fn clear_rows(&mut self) {
+        let rows = self.board.full_rows();
         let rows_cleared = self.board.clear_full_rows();
-        let score = self.level.scoring_config.score(&rows_cleared);
-        self.stats.update(score, &rows_cleared);
+        let score = self.level.scoring_config.score(rows_cleared);
+        self.stats.update(score, rows_cleared);
+        if rows.is_empty() {
+            self.streak = 0;
+        } else {
+            let clear = self.describe_clear(rows.len());
+            self.emit(GameEvent::LinesCleared { count: rows.len(), rows });
+            self.emit(GameEvent::Cleared { clear });
+        }
+        if score > 0 {
+            self.emit(GameEvent::Scored { points: score, score: self.stats.score });
+        }
         self.update_level();
-        self.current_piece = self.next_piece;
-        self.next_piece = Piece::random(Point::new(4, 18));
-        if !self.board.can_apply(&self.current_piece.points) {
+        if self.entry_delay == 0 {
+            self.bring_in_next_piece();
+        } else {
+            self.phase = Phase::Entry { frames_left: self.entry_delay };
+        }
+    }
+
+    // Works out the spin, combo and back to back that went with a clear
+    fn describe_clear(&mut self, lines: usize) -> Clear {
+        let spin = self.rotated;
+        let difficult = spin || lines >= 4;
+        let clear = Clear {
+            lines,
+            spin,
+            combo: self.streak,
+            back_to_back: difficult && self.back_to_back,
+            all_clear: self.board.rows.iter().flatten().all(|cell| cell.value != Color::Black),
+        };
+        self.streak += 1;
+        self.back_to_back = difficult;
+        clear
+    }
+
+    fn bring_in_next_piece(&mut self) {
+        self.phase = Phase::Falling;
+        self.rotated = false;
+        // clearing lines holds the garbage back
+        if self.streak == 0 && !self.raise_garbage() {
+            self.game_over = true;
+            self.emit(GameEvent::GameOver);
+            return
+        }
+        // pieces are always dealt in the same order, however many are previewed
+        self.upcoming.push(spawn_piece(&mut self.piece_set, &self.board, &mut self.rng));
+        let next_piece = self.upcoming.remove(0);
+        self.current_piece = std::mem::replace(&mut self.next_piece, next_piece);
+        if self.board.can_apply(&self.current_piece.points) {
+            self.piece_spawned();
+            self.emit(GameEvent::PieceSpawned { piece: self.current_piece.clone() });
+        } else {
             self.game_over = true;
+            self.emit(GameEvent::GameOver);
+        }
+    }
+
+    /// Queues up rows of garbage with a hole in column `hole`, to come up
+    /// under the board once a piece locks without clearing anything.
+    pub fn add_garbage(&mut self, rows: usize, hole: usize) {
+        self.garbage.push(Garbage { rows, hole });
+    }
+
+    // Raises the garbage that's come in, and says whether the
+    // board kept within the top
+    fn raise_garbage(&mut self) -> bool {
+        if self.garbage.is_empty() { return true }
+        let rows = self.garbage.rows();
+        let fits = self.garbage.raise(&mut self.board).is_some();
+        self.emit(GameEvent::GarbageRaised { rows });
+        fits
+    }
+
+    // Starts keeping track of the keys for the new piece, and in
+    // training, picks out where it should go
+    fn piece_spawned(&mut self) {
+        self.spawned = (self.finesse || self.training).then(|| self.current_piece.clone());
+        self.inputs.clear();
+        if self.training {
+            self.target = Hint::new(self, &Weights::default());
         }
     }
 
@@ -168,13 +492,27 @@ impl Game {
         }
 
         if self.levels.is_empty() {
+            self.completed = true;
             self.quit()
         } else {
             self.level = self.levels.remove(0);
+            self.emit(GameEvent::LevelUp { level: self.level.number });
         }
     }
 }
 
+// Without a piece set, the classic tetrominoes are used. They come
+// in just left of center, two rows down: (4, 18) on a standard board.
+fn spawn_piece(piece_set: &mut Option<PieceSet>, board: &Matrix<Cell>, rng: &mut Pcg64) -> Piece {
+    match piece_set {
+        Some(set) => set.random(board.width(), board.height(), rng),
+        None => {
+            let origin = Point::new(board.width() as i32 / 2 - 1, board.height() as i32 - 2);
+            Piece::random(origin, rng)
+        },
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -183,7 +521,7 @@ mod tests {
     fn setup(piece: Piece) -> Game {
         let mut game = Game::default();
         let mut matrix = Matrix::empty();
-        matrix.apply(piece, 14).unwrap();
+        matrix.apply(&piece, 14).unwrap();
         game.current_piece = piece;
         game.board = matrix;
         game
@@ -318,4 +656,289 @@ mod tests {
         assert_eq!(game.board.get(8, 2).unwrap().value, Color::Gray);
         assert_eq!(game.board.get(9, 2).unwrap().value, Color::Gray);
     }
+
+    #[test]
+    fn test_piece_set_board_size() {
+        let pentominoes = PieceSet::built_in().remove(0);
+        let config = Config { piece_set: Some(pentominoes), ..Config::default() };
+        let game = Game::new(config);
+        assert_eq!(game.board.width(), 12);
+        assert_eq!(game.board.height(), 20);
+        assert_eq!(game.current_piece.points.len(), 5);
+        assert!(game.board.can_apply(&game.current_piece.points));
+    }
+
+    #[test]
+    fn test_clearing_more_than_five_rows() {
+        let big = PieceSet::built_in().into_iter().find(|set| set.name == "Big").unwrap();
+        let i = std::sync::Arc::clone(big.pieces.iter().find(|def| def.name == "I").unwrap());
+        let mut game = Game::new(Config { piece_set: Some(big), ..Config::default() });
+        game.line_clear_frames = 0;
+        game.entry_delay = 0;
+        game.current_piece = i.spawn(game.board.width(), game.board.height());
+        // stood on end it's 8 tall, so it needs room above to turn
+        (0..6).for_each(|_| game.on_down());
+        game.on_rotate_clockwise();
+        let five = game.level.scoring_config.five;
+        let ghost = game.ghost_piece();
+        let rows: std::collections::HashSet<_> = ghost.points.iter().map(|p| p.y).collect();
+        for y in &rows {
+            for x in 0..game.board.width() as i32 {
+                if !ghost.points.contains(&Point::new(x, *y)) {
+                    game.board.set_at(&Point::new(x, *y), Cell::black(1));
+                }
+            }
+        }
+        while game.stats.pieces == 0 {
+            game.on_down();
+        }
+        assert_eq!(rows.len(), 8);
+        assert_eq!(game.stats.rows_cleared, 8);
+        assert_eq!(game.stats.score, five);
+    }
+
+    #[test]
+    fn test_board_size_override() {
+        let config = Config { board_width: Some(8), board_height: Some(16), ..Config::default() };
+        let game = Game::new(config);
+        assert_eq!(game.board.width(), 8);
+        assert_eq!(game.board.height(), 16);
+        assert!(game.current_piece.points.iter().all(|p| (3..=6).contains(&p.x) && p.y >= 14));
+    }
+
+    #[test]
+    fn test_preview_deals_the_same_pieces() {
+        let config = |preview| Config { seed: Some(9), preview, ..Config::default() };
+        let mut one = Game::new(config(1));
+        let mut five = Game::new(config(5));
+        assert_eq!(five.upcoming.len(), 4);
+        assert_eq!(one.next_piece, five.next_piece);
+        (0..200).for_each(|_| {
+            one.on_down();
+            five.on_down();
+        });
+        assert_eq!(one.board, five.board);
+        assert_eq!(one.next_piece, five.next_piece);
+    }
+
+    #[test]
+    fn test_ghost_piece() {
+        let piece = Piece::hero(Point::new(4, 10));
+        let mut game = setup(piece);
+        game.board.set(5, 3, Cell::black(1));
+        let ghost = game.ghost_piece();
+        assert_eq!(ghost.points, Piece::hero(Point::new(4, 4)).points);
+    }
+
+    #[test]
+    fn test_on_rotate_180() {
+        let origin = Point::new(4, 10);
+        let piece = Piece::teewee(origin);
+        let mut game = setup(piece);
+
+        game.on_rotate_180();
+
+        assert_eq!(game.current_piece.orientation, piece::Orientation::Three);
+        assert_eq!(game.board.get(4, 11).unwrap().value, Color::Gray);
+        assert_eq!(game.board.get(5, 11).unwrap().value, Color::Gray);
+        assert_eq!(game.board.get(6, 11).unwrap().value, Color::Gray);
+        assert_eq!(game.board.get(5, 12).unwrap().value, Color::Gray);
+        assert_eq!(game.board.get(5, 10).unwrap().value, Color::White);
+    }
+
+    #[test]
+    fn test_on_rotate_180_does_not_need_quarter_turn_room() {
+        let origin = Point::new(4, 0);
+        let piece = Piece::hero(origin);
+        let mut game = setup(piece);
+
+        // the vertical quarter turn would poke below the floor
+        game.on_rotate_clockwise();
+        assert_eq!(game.current_piece.orientation, piece::Orientation::One);
+
+        game.on_rotate_180();
+        assert_eq!(game.current_piece.orientation, piece::Orientation::Three);
+    }
+
+    #[test]
+    fn test_line_clear_and_entry_delay() {
+        let piece = Piece::hero(Point::new(4, 0));
+        let mut game = setup(piece);
+        for x in (0..4).chain(8..10) {
+            game.board.set(x, 0, Cell::black(1));
+        }
+        game.line_clear_frames = 3;
+        game.entry_delay = 2;
+
+        game.on_down();
+        assert_eq!(game.phase, Phase::Clearing { rows: vec![0], frames_left: 3 });
+        // nothing moves while the row dissolves
+        let piece = game.current_piece.clone();
+        game.on_left();
+        assert_eq!(game.current_piece, piece);
+
+        game.on_tick();
+        game.on_tick();
+        assert_eq!(game.stats.rows_cleared, 0);
+        game.on_tick();
+        assert_eq!(game.stats.rows_cleared, 1);
+        assert_eq!(game.board.get(0, 0).unwrap().value, Color::White);
+        assert_eq!(game.phase, Phase::Entry { frames_left: 2 });
+
+        game.on_tick();
+        game.on_tick();
+        assert_eq!(game.phase, Phase::Falling);
+        assert_ne!(game.current_piece, piece);
+    }
+
+    #[test]
+    fn test_step_events() {
+        let piece = Piece::hero(Point::new(4, 1));
+        let mut game = setup(piece.clone());
+        for x in (0..4).chain(8..10) {
+            game.board.set(x, 0, Cell::black(1));
+        }
+
+        // nothing moves while paused
+        assert!(game.step(&[Action::Pause, Action::MoveLeft, Action::Pause]).is_empty());
+        let piece = piece.project_down();
+        assert_eq!(game.step(&[Action::SoftDrop]), [GameEvent::PieceMoved { piece: piece.clone() }]);
+        let events = game.step(&[Action::SoftDrop]);
+        assert_eq!(
+            events,
+            [
+                GameEvent::PieceLocked { piece },
+                GameEvent::LinesCleared { count: 1, rows: vec![0] },
+                GameEvent::Cleared { clear: Clear { lines: 1, all_clear: true, ..Clear::default() } },
+                GameEvent::Scored { points: 40, score: 40 },
+                GameEvent::PieceSpawned { piece: game.current_piece.clone() },
+            ],
+        );
+        // events aren't collected outside of a step
+        game.on_left();
+        assert!(game.events.is_none());
+    }
+
+    #[test]
+    fn test_step_game_over() {
+        let mut game = setup(Piece::hero(Point::new(4, 0)));
+        for y in 15..20 {
+            for x in 0..9 {
+                game.board.set(x, y, Cell::black(1));
+            }
+        }
+        let events = game.step(&[Action::SoftDrop]);
+        assert_eq!(events.last(), Some(&GameEvent::GameOver));
+        assert!(game.game_over);
+        assert!(game.step(&[Action::MoveLeft]).is_empty());
+    }
+
+    // Soft drops the piece until it locks, or goes back up in training
+    fn drop_piece(game: &mut Game, inputs: &[Action]) -> Vec<GameEvent> {
+        let mut events = game.step(inputs);
+        let pieces = game.stats.pieces;
+        while game.stats.pieces == pieces && !events.iter().any(|event| matches!(event, GameEvent::PieceRetried { .. })) {
+            events.extend(game.step(&[Action::SoftDrop]));
+        }
+        events
+    }
+
+    #[test]
+    fn test_finesse_faults() {
+        let mut game = Game::new(Config { seed: Some(3), finesse: true, ..Config::default() });
+        // there and back again is two presses more than dropping straight down
+        let events = drop_piece(&mut game, &[Action::MoveRight, Action::MoveLeft]);
+        assert!(events.contains(&GameEvent::FinesseFault { presses: 3, fewest: 1 }), "{events:?}");
+        assert_eq!(game.stats.finesse_faults, 1);
+
+        drop_piece(&mut game, &[Action::MoveLeft]);
+        assert_eq!(game.stats.finesse_faults, 1);
+    }
+
+    #[test]
+    fn test_training() {
+        let mut game = Game::new(Config { seed: Some(3), training: true, ..Config::default() });
+        let target = game.target.clone().unwrap();
+        let spawned = game.current_piece.clone();
+        let events = drop_piece(&mut game, &[Action::MoveRight, Action::MoveLeft]);
+        assert!(events.contains(&GameEvent::PieceRetried { piece: spawned.clone() }));
+        assert_eq!(game.current_piece, spawned);
+        assert_eq!(game.stats.pieces, 0);
+        assert_eq!(game.stats.finesse_faults, 1);
+
+        target.keys.iter().for_each(|action| game.on_action(*action));
+        assert_eq!(game.stats.pieces, 1);
+        assert_eq!(game.stats.finesse_faults, 1);
+        assert!(target.piece.points.iter().all(|p| game.board.get_at(p).unwrap().value == Color::Black));
+        assert!(game.target.as_ref().is_some_and(|target| target.is_for(&game)));
+    }
+
+    #[test]
+    fn test_garbage() {
+        let mut game = Game::new(Config { seed: Some(3), ..Config::default() });
+        game.add_garbage(2, 2);
+        let events = drop_piece(&mut game, &[Action::MoveRight, Action::MoveRight, Action::MoveRight]);
+        assert!(events.contains(&GameEvent::GarbageRaised { rows: 2 }));
+        assert!(game.garbage.is_empty());
+        for y in 0..2 {
+            assert_eq!(game.board.get(2, y).unwrap().value, Color::White);
+            assert_eq!(game.board.get(0, y).unwrap(), Cell::black(matrix::GARBAGE_COLOR));
+        }
+        assert!(!game.game_over);
+
+        game.add_garbage(20, 0);
+        let events = drop_piece(&mut game, &[]);
+        assert_eq!(events.last(), Some(&GameEvent::GameOver));
+    }
+
+    #[test]
+    fn test_completing_the_last_level() {
+        let mut game = Game::new(Config { initial_level: Level::level_count() - 1, ..Config::default() });
+        game.stats.rows_cleared = game.level.rows_to_pass;
+        game.update_level();
+        assert!(game.completed && game.should_quit());
+        assert!(!game.game_over);
+    }
+
+    #[test]
+    fn test_spin() {
+        let mut game = setup(Piece::hero(Point::new(4, 0)));
+        game.on_rotate_180();
+        // wall the piece in where it's turned to, with its row full around it
+        let points = game.current_piece.points.clone();
+        for p in &points {
+            for x in 0..10 {
+                let p = Point::new(x, p.y);
+                if !points.contains(&p) {
+                    game.board.set_at(&p, Cell::black(1));
+                }
+            }
+            game.board.set_at(&Point::new(p.x, p.y + 1), Cell::black(1));
+        }
+        game.add_garbage(2, 5);
+
+        let events = game.step(&[Action::SoftDrop]);
+        let clear = events.iter().find_map(|event| match event {
+            GameEvent::Cleared { clear } => Some(*clear),
+            _ => None,
+        });
+        assert!(clear.is_some_and(|clear| clear.spin && !clear.all_clear), "{events:?}");
+        // clearing lines holds the garbage back
+        assert_eq!(game.garbage.rows(), 2);
+    }
+
+    #[test]
+    fn test_combo_and_back_to_back() {
+        let mut game = Game { rotated: true, ..Game::default() };
+        assert_eq!(game.describe_clear(2), Clear { lines: 2, spin: true, all_clear: true, ..Clear::default() });
+        game.rotated = false;
+        let clear = game.describe_clear(4);
+        assert_eq!((clear.combo, clear.back_to_back), (1, true));
+        let clear = game.describe_clear(1);
+        assert_eq!((clear.combo, clear.back_to_back), (2, false));
+
+        game.streak = 0;
+        let clear = game.describe_clear(4);
+        assert_eq!((clear.combo, clear.back_to_back), (0, false));
+    }
 }
@@ -0,0 +1,56 @@
+use serde::{Deserialize, Serialize};
+
+// Everything a player can do to a game in progress. Input from the
+// keyboard or a replay file is turned into these before reaching `Game`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    MoveLeft,
+    MoveRight,
+    SoftDrop,
+    RotateClockwise,
+    RotateCounterclockwise,
+    #[serde(rename = "rotate_180")]
+    Rotate180,
+    Pause,
+}
+
+impl Action {
+    pub fn all() -> [Self; 7] {
+        [
+            Action::MoveLeft,
+            Action::MoveRight,
+            Action::SoftDrop,
+            Action::RotateClockwise,
+            Action::RotateCounterclockwise,
+            Action::Rotate180,
+            Action::Pause,
+        ]
+    }
+
+    // As written in the settings file
+    pub fn name(&self) -> &'static str {
+        match self {
+            Action::MoveLeft => "move_left",
+            Action::MoveRight => "move_right",
+            Action::SoftDrop => "soft_drop",
+            Action::RotateClockwise => "rotate_clockwise",
+            Action::RotateCounterclockwise => "rotate_counterclockwise",
+            Action::Rotate180 => "rotate_180",
+            Action::Pause => "pause",
+        }
+    }
+
+    // As shown on screen
+    pub fn label(&self) -> &'static str {
+        match self {
+            Action::MoveLeft => "Move left",
+            Action::MoveRight => "Move right",
+            Action::SoftDrop => "Soft drop",
+            Action::RotateClockwise => "Rotate clockwise",
+            Action::RotateCounterclockwise => "Rotate counterclockwise",
+            Action::Rotate180 => "Rotate 180",
+            Action::Pause => "Pause",
+        }
+    }
+}
@@ -0,0 +1,31 @@
+use serde::{Deserialize, Serialize};
+
+use super::{garbage::Clear, piece::Piece};
+
+/// Something that happened during [`Game::step`](super::Game::step), in the
+/// order it happened, so UIs, sound and network layers can react to changes
+/// rather than compare the game's fields before and after.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GameEvent {
+    // a new piece came in at the top
+    PieceSpawned { piece: Piece },
+    // the piece moved or turned, by the player or by falling
+    PieceMoved { piece: Piece },
+    // the piece settled onto the board where it is
+    PieceLocked { piece: Piece },
+    // full rows were taken out, numbered up from the floor as they were before clearing
+    LinesCleared { count: usize, rows: Vec<usize> },
+    // what went with the clear, for working out the garbage it sends
+    Cleared { clear: Clear },
+    LevelUp { level: usize },
+    // points added for a clear, and the score after them
+    Scored { points: usize, score: usize },
+    // the piece took more key presses than the fewest that would have put it there
+    FinesseFault { presses: usize, fewest: usize },
+    // in training, the piece went back to the top to be tried again
+    PieceRetried { piece: Piece },
+    // rows of garbage came up from the bottom of the board
+    GarbageRaised { rows: usize },
+    GameOver,
+}
@@ -0,0 +1,190 @@
+use std::collections::VecDeque;
+use rand::SeedableRng;
+use rand_pcg::Pcg64;
+use serde::{Deserialize, Serialize};
+
+use super::matrix::{Cell, Matrix};
+
+/// What went with a clear, beyond the lines themselves, for working out
+/// how much garbage it sends.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct Clear {
+    pub lines: usize,
+    // the piece was turned into a spot it can't move out of sideways or up
+    pub spin: bool,
+    // how many clears came straight before this one, piece after piece
+    pub combo: usize,
+    // a tetris or a spin, straight after another
+    pub back_to_back: bool,
+    // nothing was left on the board
+    pub all_clear: bool,
+}
+
+/// How many rows of garbage each sort of clear sends.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AttackTable {
+    // by the number of lines cleared, and as many as the lines past the end
+    pub lines: Vec<usize>,
+    // the same, for spins, and twice the lines past the end
+    pub spins: Vec<usize>,
+    // added for each clear into a combo, the last for every one after
+    pub combos: Vec<usize>,
+    pub back_to_back: usize,
+    pub all_clear: usize,
+}
+
+impl Default for AttackTable {
+    fn default() -> Self {
+        Self {
+            lines: vec![0, 0, 1, 2, 4],
+            spins: vec![0, 2, 4, 6],
+            combos: vec![0, 1, 1, 2, 2, 3, 3, 4, 4, 4, 5],
+            back_to_back: 1,
+            all_clear: 10,
+        }
+    }
+}
+
+impl AttackTable {
+    pub fn attack(&self, clear: &Clear) -> usize {
+        if clear.lines == 0 { return 0 }
+        let base = if clear.spin {
+            self.spins.get(clear.lines).copied().unwrap_or(clear.lines * 2)
+        } else {
+            self.lines.get(clear.lines).copied().unwrap_or(clear.lines)
+        };
+        let combo = self.combos
+            .get(clear.combo)
+            .or(self.combos.last())
+            .copied()
+            .unwrap_or(0);
+        let back_to_back = if clear.back_to_back { self.back_to_back } else { 0 };
+        let all_clear = if clear.all_clear { self.all_clear } else { 0 };
+        base + combo + back_to_back + all_clear
+    }
+}
+
+/// Rows of garbage sent in one attack, all with their hole in one column
+/// unless the queue's messiness moves it.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Garbage {
+    pub rows: usize,
+    pub hole: usize,
+}
+
+/// The garbage waiting to come up under a board, oldest first. It comes
+/// up once a piece locks without clearing anything, and until then the
+/// player's own attacks can cancel it out.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct GarbageQueue {
+    attacks: VecDeque<Garbage>,
+    // the chance of each row's hole moving from the one below it
+    pub messiness: f64,
+    // apart from the game's own, so the pieces are dealt the same
+    // however much garbage comes in
+    rng: Pcg64,
+}
+
+impl Default for GarbageQueue {
+    fn default() -> Self {
+        Self::new(0)
+    }
+}
+
+impl GarbageQueue {
+    pub fn new(seed: u64) -> Self {
+        Self { attacks: VecDeque::new(), messiness: 0.0, rng: Pcg64::seed_from_u64(seed) }
+    }
+
+    pub fn push(&mut self, garbage: Garbage) {
+        if garbage.rows > 0 {
+            self.attacks.push_back(garbage);
+        }
+    }
+
+    /// How many rows are waiting, altogether
+    pub fn rows(&self) -> usize {
+        self.attacks.iter().map(|garbage| garbage.rows).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.attacks.is_empty()
+    }
+
+    /// Takes `rows` off the oldest garbage first, and returns the rows
+    /// of the attack left over once it's all cancelled.
+    pub fn cancel(&mut self, mut rows: usize) -> usize {
+        while let Some(garbage) = self.attacks.front_mut() {
+            if rows == 0 { break }
+            let cancelled = rows.min(garbage.rows);
+            garbage.rows -= cancelled;
+            rows -= cancelled;
+            if garbage.rows == 0 {
+                self.attacks.pop_front();
+            }
+        }
+        rows
+    }
+
+    /// Raises all the waiting garbage onto `board`, and gives `None` if
+    /// anything settled was pushed off the top.
+    pub fn raise(&mut self, board: &mut Matrix<Cell>) -> Option<()> {
+        let attacks = std::mem::take(&mut self.attacks);
+        // every row goes up, even once the board's overflowed
+        let overflowed = attacks
+            .iter()
+            .filter(|garbage| board.insert_garbage(garbage.rows, garbage.hole, self.messiness, &mut self.rng).is_none())
+            .count();
+        (overflowed == 0).then_some(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_attack() {
+        let table = AttackTable::default();
+        let attack = |lines, spin, combo, back_to_back, all_clear| {
+            table.attack(&Clear { lines, spin, combo, back_to_back, all_clear })
+        };
+        assert_eq!([0, 1, 2, 3, 4, 5].map(|lines| attack(lines, false, 0, false, false)), [0, 0, 1, 2, 4, 5]);
+        assert_eq!(attack(2, true, 0, false, false), 4);
+        assert_eq!(attack(5, true, 0, false, false), 10);
+        assert_eq!(attack(4, false, 0, true, false), 5);
+        assert_eq!(attack(1, false, 3, false, false), 2);
+        assert_eq!(attack(1, false, 40, false, false), 5);
+        assert_eq!(attack(1, false, 0, false, true), 10);
+        assert_eq!(attack(0, true, 5, true, false), 0);
+    }
+
+    #[test]
+    fn test_cancel() {
+        let mut queue = GarbageQueue::new(1);
+        queue.push(Garbage { rows: 2, hole: 0 });
+        queue.push(Garbage { rows: 0, hole: 1 });
+        queue.push(Garbage { rows: 3, hole: 4 });
+        assert_eq!(queue.rows(), 5);
+
+        assert_eq!(queue.cancel(3), 0);
+        assert_eq!(queue.attacks, [Garbage { rows: 2, hole: 4 }]);
+        assert_eq!(queue.cancel(4), 2);
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn test_raise() {
+        let mut board = Matrix::empty_sized(10, 6);
+        let mut queue = GarbageQueue::new(1);
+        queue.push(Garbage { rows: 2, hole: 3 });
+        queue.push(Garbage { rows: 1, hole: 7 });
+        assert_eq!(queue.raise(&mut board), Some(()));
+        assert!(queue.is_empty());
+        let holes: Vec<_> = (0..3).map(|y| (0..10).find(|x| board.get(*x, y) == Some(Cell::white()))).collect();
+        assert_eq!(holes, [Some(7), Some(3), Some(3)]);
+
+        queue.push(Garbage { rows: 4, hole: 0 });
+        assert_eq!(queue.raise(&mut board), None);
+    }
+}
@@ -1,3 +1,4 @@
+use serde::{Deserialize, Serialize};
 use super::{
     piece::{Shape, Piece},
     scoring::ScoringConfig,
@@ -5,6 +6,12 @@ use super::{
 
 const NUM_LEVELS: usize = 10;
 const MAX_TICKS_PER_DROP: usize = 61;
+// Colors for pieces beyond the classic seven that don't bring their own,
+// enough for the largest built-in set (the 18 one-sided pentominoes).
+// They're picked from the 256-color palette to sit with each theme's
+// other colors, and handed out by a piece's place in its set, wrapping
+// round for bigger sets.
+const EXTRA_COLORS: usize = 18;
 const THEMES: [Theme; 10] = [
     Theme {
         orange_ricky: 214,
@@ -13,7 +20,11 @@ const THEMES: [Theme; 10] = [
         rhode_island_z: 2,
         hero: 14,
         smashboy: 11,
-        teewee: 56
+        teewee: 56,
+        extra: [
+            202, 208, 51, 214, 27, 40, 34, 220, 226,
+            129, 197, 33, 165, 231, 118, 82, 196, 46,
+        ]
     },
     Theme {
         orange_ricky: 166,
@@ -22,7 +33,11 @@ const THEMES: [Theme; 10] = [
         rhode_island_z: 64,
         hero: 39,
         smashboy: 227,
-        teewee: 57
+        teewee: 57,
+        extra: [
+            166, 172, 39, 178, 61, 64, 70, 227, 186,
+            57, 161, 68, 127, 255, 106, 76, 88, 113,
+        ]
     },
     Theme {
         orange_ricky: 172,
@@ -31,7 +46,11 @@ const THEMES: [Theme; 10] = [
         rhode_island_z: 76,
         hero: 45,
         smashboy: 229,
-        teewee: 53
+        teewee: 53,
+        extra: [
+            172, 179, 45, 215, 69, 76, 71, 229, 223,
+            53, 168, 75, 133, 254, 149, 114, 160, 120,
+        ]
     },
     Theme {
         orange_ricky: 178,
@@ -40,7 +59,11 @@ const THEMES: [Theme; 10] = [
         rhode_island_z: 78,
         hero: 41,
         smashboy: 227,
-        teewee: 55
+        teewee: 55,
+        extra: [
+            178, 136, 41, 221, 27, 78, 72, 227, 222,
+            55, 162, 33, 134, 253, 155, 119, 161, 84,
+        ]
     },
     Theme {
         orange_ricky: 167,
@@ -49,7 +72,11 @@ const THEMES: [Theme; 10] = [
         rhode_island_z: 71,
         hero: 51,
         smashboy: 220,
-        teewee: 52
+        teewee: 52,
+        extra: [
+            167, 131, 51, 209, 20, 71, 65, 220, 214,
+            52, 125, 26, 90, 252, 107, 77, 124, 83,
+        ]
     },
     Theme {
         orange_ricky: 215,
@@ -58,7 +85,11 @@ const THEMES: [Theme; 10] = [
         rhode_island_z: 34,
         hero: 44,
         smashboy: 184,
-        teewee: 99
+        teewee: 99,
+        extra: [
+            215, 180, 44, 216, 153, 34, 29, 184, 228,
+            99, 205, 111, 170, 251, 148, 42, 163, 49,
+        ]
     },
     Theme {
         orange_ricky: 130,
@@ -67,7 +98,11 @@ const THEMES: [Theme; 10] = [
         rhode_island_z: 10,
         hero: 50,
         smashboy: 190,
-        teewee: 92
+        teewee: 92,
+        extra: [
+            130, 94, 50, 173, 62, 10, 28, 190, 191,
+            92, 199, 63, 128, 250, 112, 47, 196, 48,
+        ]
     },
     Theme {
         orange_ricky: 58,
@@ -76,7 +111,11 @@ const THEMES: [Theme; 10] = [
         rhode_island_z: 65,
         hero: 75,
         smashboy: 101,
-        teewee: 96
+        teewee: 96,
+        extra: [
+            58, 95, 75, 137, 61, 65, 59, 101, 143,
+            96, 132, 67, 97, 249, 108, 71, 52, 72,
+        ]
     },
     Theme {
         orange_ricky: 136,
@@ -85,7 +124,11 @@ const THEMES: [Theme; 10] = [
         rhode_island_z: 119,
         hero: 123,
         smashboy: 185,
-        teewee: 183
+        teewee: 183,
+        extra: [
+            136, 137, 123, 179, 67, 119, 114, 185, 187,
+            183, 175, 111, 176, 248, 150, 121, 125, 158,
+        ]
     },
     Theme {
         orange_ricky: 179,
@@ -94,11 +137,16 @@ const THEMES: [Theme; 10] = [
         rhode_island_z: 144,
         hero: 195,
         smashboy: 230,
-        teewee: 225
+        teewee: 225,
+        extra: [
+            179, 180, 195, 223, 147, 144, 151, 230, 229,
+            225, 218, 153, 219, 255, 193, 157, 162, 194,
+        ]
     },
 ];
 
-#[derive(Copy, Clone)]
+/// How fast pieces fall, what clears score and how many rows lead on to the next level.
+#[derive(Copy, Clone, Serialize, Deserialize)]
 pub struct Level {
     pub ticks_per_drop: usize,
     pub counter: usize,
@@ -130,8 +178,9 @@ impl Level {
                 number * 100,
                 number * 300,
                 number * 1200,
+                number * 3000,
             ),
-            theme: THEMES[number - 1]
+            theme: Theme::number(number)
         }
     }
 
@@ -146,7 +195,7 @@ impl Level {
     }
 }
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Serialize, Deserialize)]
 pub struct Theme {
     pub orange_ricky: u8,
     pub blue_ricky: u8,
@@ -155,11 +204,19 @@ pub struct Theme {
     pub hero: u8,
     pub smashboy: u8,
     pub teewee: u8,
+    pub extra: [u8; EXTRA_COLORS],
 }
 
 impl Theme {
+    pub fn count() -> usize { THEMES.len() }
+
+    // Counting from 1, like the levels that use them
+    pub fn number(n: usize) -> Self {
+        THEMES[n - 1]
+    }
+
     pub fn piece_color(&self, piece: &Piece) -> u8 {
-        match piece.shape {
+        match &piece.shape {
             Shape::OrangeRicky => self.orange_ricky,
             Shape::BlueRicky => self.blue_ricky,
             Shape::ClevelandZ => self.cleveland_z,
@@ -167,6 +224,9 @@ impl Theme {
             Shape::Hero => self.hero,
             Shape::Smashboy => self.smashboy,
             Shape::Teewee => self.teewee,
+            Shape::Defined(def) => def.color.unwrap_or(
+                self.extra[def.index % EXTRA_COLORS]
+            ),
         }
     }
 }
@@ -1,18 +1,20 @@
-use rand::{thread_rng, Rng};
-use super::scoring::RowsCleared;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
 use super::piece::{Piece, Point};
 
-const WIDTH: usize = 10;
-const HEIGHT: usize = 20;
+pub const WIDTH: usize = 10;
+pub const HEIGHT: usize = 20;
+// garbage rows are drawn in gray
+pub const GARBAGE_COLOR: u8 = 8;
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub enum Color {
     Black,
     White,
     Gray,
 }
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Cell {
     pub value: Color,
     pub color: u8,
@@ -41,7 +43,8 @@ impl Cell {
     }
 }
 
-#[derive(PartialEq)]
+/// A grid of rows, the bottom row first, so `y` counts up from the floor.
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
 pub struct Matrix<T> {
     pub rows: Vec<Vec<T>>,
 }
@@ -71,6 +74,31 @@ impl <T: Copy> Matrix<T> {
         self.rows[0].len()
     }
 
+    // Whether a (possibly negative) point falls on the matrix
+    pub fn contains(&self, p: &Point) -> bool {
+        self.index_of(p).is_some()
+    }
+
+    pub fn get_at(&self, p: &Point) -> Option<T> {
+        let (x, y) = self.index_of(p)?;
+        self.get(x, y)
+    }
+
+    pub fn set_at(&mut self, p: &Point, val: T) -> Option<T> {
+        let (x, y) = self.index_of(p)?;
+        self.set(x, y, val)
+    }
+
+    fn index_of(&self, p: &Point) -> Option<(usize, usize)> {
+        let x = usize::try_from(p.x).ok()?;
+        let y = usize::try_from(p.y).ok()?;
+        if x < self.width() && y < self.height() {
+            Some((x, y))
+        } else {
+            None
+        }
+    }
+
     pub fn height(&self) -> usize {
         self.rows.len()
     }
@@ -78,25 +106,31 @@ impl <T: Copy> Matrix<T> {
 
 impl Matrix<Cell> {
     pub fn empty() -> Self {
-        Matrix::new(WIDTH, HEIGHT, Cell::white())
+        Self::empty_sized(WIDTH, HEIGHT)
+    }
+
+    pub fn empty_sized(width: usize, height: usize) -> Self {
+        Matrix::new(width, height, Cell::white())
     }
 
-    pub fn random_partial_fill() -> Self {
-        let empty_row = vec![Cell::white(); WIDTH];
-        let mut rows = vec![empty_row; HEIGHT];
-        (0..HEIGHT).for_each(|i| {
-            if i < HEIGHT - 15 {
-                rows[i] = Self::random_row();
+    // Fill the bottom rows with random garbage, leaving
+    // the top 15 rows clear.
+    pub fn random_partial_fill<R: Rng>(width: usize, height: usize, rng: &mut R) -> Self {
+        let empty_row = vec![Cell::white(); width];
+        let mut rows = vec![empty_row; height];
+        (0..height).for_each(|i| {
+            if i + 15 < height {
+                rows[i] = Self::random_row(width, rng);
             }
         });
 
         Matrix { rows }
     }
 
-    fn random_row() -> Vec<Cell> {
-        let mut row = vec![Cell::white(); WIDTH];
-        row.iter_mut().take(WIDTH).for_each(|i| {
-            if thread_rng().gen_bool(1.0 / 3.0) {
+    fn random_row<R: Rng>(width: usize, rng: &mut R) -> Vec<Cell> {
+        let mut row = vec![Cell::white(); width];
+        row.iter_mut().for_each(|i| {
+            if rng.gen_bool(1.0 / 3.0) {
                 // todo: set random color as well
                 i.value = Color::Black
             }
@@ -104,13 +138,40 @@ impl Matrix<Cell> {
         row
     }
 
+    // Raises the board a row, filling the bottom one in but for a hole
+    // at `hole`. None if that pushes settled cells off the top.
+    pub fn push_garbage(&mut self, hole: usize) -> Option<()> {
+        let top = self.rows.remove(self.height() - 1);
+        let row = (0..self.width())
+            .map(|x| if x == hole { Cell::white() } else { Cell::black(GARBAGE_COLOR) })
+            .collect();
+        self.rows.insert(0, row);
+        (!top.iter().any(|cell| cell.value == Color::Black)).then_some(())
+    }
+
+    // Pushes up `rows` of garbage with their hole in column `hole`. Each
+    // row after the first has a `messiness` chance, from 0 to 1, of its
+    // hole moving to another column, and the rows after it follow that one.
+    pub fn insert_garbage<R: Rng>(&mut self, rows: usize, hole: usize, messiness: f64, rng: &mut R) -> Option<()> {
+        let width = self.width();
+        let mut hole = hole.min(width - 1);
+        let mut fits = true;
+        for row in 0..rows {
+            if row > 0 && width > 1 && rng.gen_bool(messiness.clamp(0.0, 1.0)) {
+                hole = (hole + rng.gen_range(1..width)) % width;
+            }
+            fits &= self.push_garbage(hole).is_some();
+        }
+        fits.then_some(())
+    }
+
     fn unset(&mut self, x: usize, y: usize) {
         let mut row = self.rows.get(y).unwrap().to_owned();
         row[x] = Cell::white();
         self.rows[y] = row;
     }
 
-    pub fn apply(&mut self, piece: Piece, color: u8) -> Option<&Self> {
+    pub fn apply(&mut self, piece: &Piece, color: u8) -> Option<&Self> {
         if !self.can_apply(&piece.points) { return None }
 
         let greys = self.rows
@@ -119,14 +180,14 @@ impl Matrix<Cell> {
             .fold(vec![], |mut acc, (y, row)| {
                 row.iter().enumerate().for_each(|(x, cell)| {
                     if cell.value == Color::Gray {
-                        acc.push(Point::new(x, y));
+                        acc.push((x, y));
                     }
                 });
                 acc
             });
         greys.iter()
-            .for_each(|p| {
-                self.set(p.x, p.y, Cell::white());
+            .for_each(|(x, y)| {
+                self.set(*x, *y, Cell::white());
             });
 
         piece.points
@@ -136,7 +197,7 @@ impl Matrix<Cell> {
                     value: Color::Gray,
                     color
                 };
-                self.set(p.x, p.y, val);
+                self.set_at(p, val);
             });
 
         Some(self)
@@ -151,13 +212,14 @@ impl Matrix<Cell> {
                     value: Color::Black,
                     color,
                 };
-                self.set(p.x, p.y, val);
+                self.set_at(p, val);
             });
         Some(self)
     }
 
-    pub fn clear_full_rows(&mut self) -> RowsCleared {
-        let full_indices = self.rows
+    // Rows with no gaps left, from the bottom up
+    pub fn full_rows(&self) -> Vec<usize> {
+        self.rows
             .iter()
             .enumerate()
             .filter_map(|(y, row)| {
@@ -167,23 +229,27 @@ impl Matrix<Cell> {
                     None
                 }
             })
-            .collect::<Vec<_>>();
+            .collect()
+    }
+
+    pub fn clear_full_rows(&mut self) -> usize {
+        let full_indices = self.full_rows();
         let points_to_clear = self.rows
             .iter()
             .enumerate()
-            .fold(vec![], |mut acc: Vec<Point>, (y, row)| {
+            .fold(vec![], |mut acc: Vec<(usize, usize)>, (y, row)| {
                 if !full_indices.contains(&y) { return acc }
 
                 row.iter()
                     .enumerate()
                     .for_each(|(x, _)| {
-                        acc.push(Point::new(x, y));
+                        acc.push((x, y));
                     });
                 acc
             });
         points_to_clear
             .iter()
-            .for_each(|p| self.unset(p.x, p.y));
+            .for_each(|(x, y)| self.unset(*x, *y));
         let points_to_drop = self.rows
             .iter()
             .enumerate()
@@ -193,40 +259,34 @@ impl Matrix<Cell> {
                     .enumerate()
                     .for_each(|(x, cell)| {
                         if cell.value == Color::Black {
-                            acc.push((Point::new(x, y), cell.color));
+                            acc.push(((x, y), cell.color));
                         }
                     });
                 acc
             });
         points_to_drop
             .iter()
-            .for_each(|(p, _)| self.unset(p.x, p.y));
+            .for_each(|((x, y), _)| self.unset(*x, *y));
 
         points_to_drop
             .iter()
-            .for_each(|(p, color)| {
+            .for_each(|((x, y), color)| {
                 let drop_by = full_indices
                     .iter()
-                    .filter(|y| p.y > **y)
+                    .filter(|full_y| y > *full_y)
                     .count();
                 let val = Cell::black(*color);
-                self.set(p.x, p.y - drop_by, val);
+                self.set(*x, y - drop_by, val);
             });
 
-        match full_indices.len() {
-            1 => RowsCleared::One,
-            2 => RowsCleared::Two,
-            3 => RowsCleared::Three,
-            4 => RowsCleared::Four,
-            _ => RowsCleared::Zero
-        }
+        full_indices.len()
     }
 
     pub fn can_apply(&self, points: &[Point]) -> bool {
         points
             .iter()
             .all(|p| {
-                if let Some(cell) = self.get(p.x, p.y) {
+                if let Some(cell) = self.get_at(p) {
                     cell.value != Color::Black
                 } else {
                     false
@@ -236,9 +296,9 @@ impl Matrix<Cell> {
 }
 
 // +--+--+--+--+--+--+--+--+--+--+--+
-fn horizontal_border() -> String {
+fn horizontal_border(width: usize) -> String {
     let mut hz_border = String::from(".");
-    for _i in 0..WIDTH {
+    for _i in 0..width {
         hz_border.push_str("  .");
     }
     hz_border
@@ -252,7 +312,7 @@ impl std::fmt::Debug for Matrix<Cell> {
 
 impl std::fmt::Display for Matrix<Cell> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let mut out = format!("\r\n{}", horizontal_border());
+        let mut out = format!("\r\n{}", horizontal_border(self.width()));
 
         self.rows.iter().rev().for_each(|row| {
             out.push_str("\r\n ");
@@ -263,7 +323,7 @@ impl std::fmt::Display for Matrix<Cell> {
                     out.push_str("[] ");
                 }
             });
-            let bottom = format!("\r\n{}", horizontal_border());
+            let bottom = format!("\r\n{}", horizontal_border(self.width()));
             out.push_str(bottom.as_str());
         });
 
@@ -274,6 +334,7 @@ impl std::fmt::Display for Matrix<Cell> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use rand::SeedableRng;
 
     #[test]
     fn test_set() {
@@ -295,7 +356,18 @@ mod tests {
     fn test_apply_out_of_bounds() {
         let mut matrix = Matrix::empty();
         let piece = Piece::hero(Point::new(7, 0));
-        assert!(matrix.apply(piece, 1).is_none());
+        assert!(matrix.apply(&piece, 1).is_none());
+    }
+
+    #[test]
+    fn test_contains() {
+        let matrix = Matrix::empty();
+        assert!(matrix.contains(&Point::new(0, 0)));
+        assert!(matrix.contains(&Point::new(9, 19)));
+        assert!(!matrix.contains(&Point::new(-1, 0)));
+        assert!(!matrix.contains(&Point::new(0, -1)));
+        assert!(!matrix.contains(&Point::new(10, 0)));
+        assert!(!matrix.contains(&Point::new(0, 20)));
     }
 
     #[test]
@@ -308,7 +380,7 @@ mod tests {
         expected.set(5, 18, Cell::gray(1));
         expected.set(5, 19, Cell::gray(1));
         expected.set(6, 19, Cell::gray(1));
-        assert_eq!(*matrix.apply(piece, 1).unwrap(), expected);
+        assert_eq!(*matrix.apply(&piece, 1).unwrap(), expected);
     }
 
     #[test]
@@ -316,7 +388,7 @@ mod tests {
         let mut matrix = Matrix::empty();
         let origin = Point::new(8, 18);
         let piece = Piece::rhode_island_z(origin);
-        assert!(matrix.apply(piece, 1).is_none());
+        assert!(matrix.apply(&piece, 1).is_none());
     }
 
     #[test]
@@ -324,7 +396,7 @@ mod tests {
         let mut matrix = Matrix::empty();
         let origin = Point::new(4, 19);
         let piece = Piece::rhode_island_z(origin);
-        assert!(matrix.apply(piece, 1).is_none());
+        assert!(matrix.apply(&piece, 1).is_none());
     }
 
     #[test]
@@ -333,6 +405,47 @@ mod tests {
         matrix.set(5, 18, Cell::black(1));
         let origin = Point::new(4, 18);
         let piece = Piece::rhode_island_z(origin);
-        assert!(matrix.apply(piece, 1).is_none());
+        assert!(matrix.apply(&piece, 1).is_none());
+    }
+
+    #[test]
+    fn test_push_garbage() {
+        let mut matrix = Matrix::empty();
+        matrix.set(0, 0, Cell::black(1));
+        assert!(matrix.push_garbage(3).is_some());
+        assert_eq!(matrix.get(0, 1).unwrap(), Cell::black(1));
+        assert_eq!(matrix.get(3, 0).unwrap().value, Color::White);
+        assert_eq!(matrix.rows[0].iter().filter(|cell| cell.value == Color::Black).count(), WIDTH - 1);
+
+        matrix.set(0, HEIGHT - 1, Cell::black(1));
+        assert!(matrix.push_garbage(3).is_none());
+        assert_eq!(matrix.height(), HEIGHT);
+    }
+
+    #[test]
+    fn test_insert_garbage() {
+        let hole = |matrix: &Matrix<Cell>, y| (0..WIDTH).find(|x| matrix.get(*x, y).unwrap().value == Color::White);
+        let mut rng = rand_pcg::Pcg64::seed_from_u64(5);
+        let mut matrix = Matrix::empty();
+        assert!(matrix.insert_garbage(4, 2, 0.0, &mut rng).is_some());
+        assert!((0..4).all(|y| hole(&matrix, y) == Some(2)));
+
+        let mut matrix = Matrix::empty();
+        matrix.insert_garbage(6, 2, 1.0, &mut rng);
+        assert_eq!(hole(&matrix, 5), Some(2));
+        assert!((0..5).all(|y| hole(&matrix, y) != hole(&matrix, y + 1)));
+        assert!(matrix.insert_garbage(HEIGHT, 2, 0.5, &mut rng).is_none());
+    }
+
+    #[test]
+    fn test_clear_five_rows() {
+        let mut matrix = Matrix::empty();
+        (0..5).for_each(|y| {
+            (0..WIDTH).for_each(|x| { matrix.set(x, y, Cell::black(1)); });
+        });
+        matrix.set(0, 5, Cell::black(1));
+        assert_eq!(matrix.clear_full_rows(), 5);
+        assert_eq!(matrix.get(0, 0).unwrap().value, Color::Black);
+        assert_eq!(matrix.get(0, 5).unwrap().value, Color::White);
     }
 }
@@ -1,19 +1,21 @@
-use rand::{prelude::thread_rng, seq::SliceRandom};
-use super::rotate;
+use std::sync::Arc;
+use rand::{seq::SliceRandom, Rng};
+use serde::{Deserialize, Serialize};
+use super::{rotate, piece_set::PieceDef};
 
-#[derive(Debug, Eq, PartialEq, Ord, PartialOrd, Clone, Copy)]
+#[derive(Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Clone, Copy, Serialize, Deserialize)]
 pub struct Point {
-    pub x: usize,
-    pub y: usize
+    pub x: i32,
+    pub y: i32
 }
 
 impl Point {
-    pub fn new(x: usize, y: usize) -> Self {
+    pub fn new(x: i32, y: i32) -> Self {
         Point { x, y }
     }
 }
 
-#[derive(Debug, PartialEq, Copy, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub enum Shape {
     OrangeRicky,
     BlueRicky,
@@ -22,9 +24,10 @@ pub enum Shape {
     Hero,
     Teewee,
     Smashboy,
+    Defined(Arc<PieceDef>),
 }
 
-#[derive(Debug, PartialEq, Copy, Clone)]
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone, Serialize, Deserialize)]
 pub enum Orientation {
     One,
     Two,
@@ -33,6 +36,19 @@ pub enum Orientation {
 }
 
 impl Orientation {
+    pub fn all() -> [Self; 4] {
+        [Orientation::One, Orientation::Two, Orientation::Three, Orientation::Four]
+    }
+
+    pub fn index(&self) -> usize {
+        match self {
+            Orientation::One => 0,
+            Orientation::Two => 1,
+            Orientation::Three => 2,
+            Orientation::Four => 3,
+        }
+    }
+
     pub fn next(&self) -> Self {
         match self {
             Orientation::One => Orientation::Two,
@@ -52,40 +68,31 @@ impl Orientation {
     }
 }
 
-#[derive(Copy, Clone)]
+/// A piece's shape, which way round it is, and the board cells it covers.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub struct Piece {
     pub shape: Shape,
     pub orientation: Orientation,
-    pub points: [Point; 4],
+    pub points: Vec<Point>,
 }
 
 impl Piece {
-    pub fn new(shape: Shape, points: [Point; 4]) -> Self {
+    pub fn new(shape: Shape, points: Vec<Point>) -> Self {
         Self { shape, points, orientation: Orientation::One }
     }
 
-    pub fn random(origin: Point) -> Self {
-        let shapes = [
-            Shape::OrangeRicky,
-            Shape::BlueRicky,
-            Shape::ClevelandZ,
-            Shape::RhodeIslandZ,
-            Shape::Hero,
-            Shape::Teewee,
-            Shape::Smashboy,
+    pub fn random<R: Rng>(origin: Point, rng: &mut R) -> Self {
+        let constructors: [fn(Point) -> Self; 7] = [
+            Self::orange_ricky,
+            Self::blue_ricky,
+            Self::cleveland_z,
+            Self::rhode_island_z,
+            Self::hero,
+            Self::teewee,
+            Self::smashboy,
         ];
-        let shape = shapes.choose(&mut thread_rng())
-            .copied()
-            .unwrap();
-        match shape {
-            Shape::OrangeRicky => Self::orange_ricky(origin),
-            Shape::BlueRicky => Self::blue_ricky(origin),
-            Shape::ClevelandZ => Self::cleveland_z(origin),
-            Shape::RhodeIslandZ => Self::rhode_island_z(origin),
-            Shape::Hero => Self::hero(origin),
-            Shape::Teewee => Self::teewee(origin),
-            Shape::Smashboy => Self::smashboy(origin)
-        }
+        let construct = constructors.choose(rng).unwrap();
+        construct(origin)
     }
 
     //
@@ -93,7 +100,7 @@ impl Piece {
     // []
     //
     pub fn orange_ricky(origin: Point) -> Self {
-        let points = [
+        let points = vec![
             Point::new(origin.x, origin.y),
             Point::new(origin.x, origin.y + 1),
             Point::new(origin.x + 1, origin.y + 1),
@@ -107,7 +114,7 @@ impl Piece {
     //     []
     //
     pub fn blue_ricky(origin: Point) -> Self {
-        let points = [
+        let points = vec![
             Point::new(origin.x + 2, origin.y),
             Point::new(origin.x, origin.y + 1),
             Point::new(origin.x + 1, origin.y + 1),
@@ -121,7 +128,7 @@ impl Piece {
     //   [][]
     //
     pub fn cleveland_z(origin: Point) -> Self {
-        let points = [
+        let points = vec![
             Point::new(origin.x + 1, origin.y),
             Point::new(origin.x + 2, origin.y),
             Point::new(origin.x, origin.y + 1),
@@ -135,7 +142,7 @@ impl Piece {
     // [][]
     //
     pub fn rhode_island_z(origin: Point) -> Self {
-        let points = [
+        let points = vec![
             Point::new(origin.x, origin.y),
             Point::new(origin.x + 1, origin.y),
             Point::new(origin.x + 1, origin.y + 1),
@@ -148,7 +155,7 @@ impl Piece {
     // [][][][]
     //
     pub fn hero(origin: Point) -> Self {
-        let points = [
+        let points = vec![
             Point::new(origin.x, origin.y),
             Point::new(origin.x + 1, origin.y),
             Point::new(origin.x + 2, origin.y),
@@ -162,7 +169,7 @@ impl Piece {
     //   []
     //
     pub fn teewee(origin: Point) -> Self {
-        let points = [
+        let points = vec![
             Point::new(origin.x + 1, origin.y),
             Point::new(origin.x, origin.y + 1),
             Point::new(origin.x + 1, origin.y + 1),
@@ -176,7 +183,7 @@ impl Piece {
     // [][]
     //
     pub fn smashboy(origin: Point) -> Self {
-        let points = [
+        let points = vec![
             Point::new(origin.x, origin.y),
             Point::new(origin.x + 1, origin.y),
             Point::new(origin.x, origin.y + 1),
@@ -185,50 +192,44 @@ impl Piece {
         Self::new(Shape::Smashboy, points)
     }
 
-    pub fn project_left(&self) -> Option<Piece> {
-        let points = self.map_points(|p| {
-            let point = Point::new(p.x.checked_sub(1)?, p.y);
-            Some(point)
-        })?;
-        Some(Piece {
-            points,
-            shape: self.shape,
-            orientation: self.orientation
-        })
+    // Projections may place the piece partially off the board;
+    // it's up to the caller to check the result against the Matrix.
+    pub fn project_left(&self) -> Piece {
+        self.translate(-1, 0)
     }
 
-    pub fn project_right(&self) -> Option<Piece> {
-        let points = self.map_points(|p| {
-            let new_x = p.x + 1;
-            // todo: remove hard-coded knowledge of board width
-            if new_x > 9 { return None }
-            Some(Point::new(new_x, p.y))
-        })?;
-        Some(Piece {
-            points,
-            shape: self.shape,
-            orientation: self.orientation
-        })
+    pub fn project_right(&self) -> Piece {
+        self.translate(1, 0)
+    }
+
+    pub fn project_down(&self) -> Piece {
+        self.translate(0, -1)
     }
 
-    pub fn project_down(&self) -> Option<Piece> {
-        let points = self.map_points(|p| {
-            let point = Point::new(p.x, p.y.checked_sub(1)?);
-            Some(point)
-        })?;
-        Some(Piece {
+    pub fn translate(&self, dx: i32, dy: i32) -> Piece {
+        let points = self.points
+            .iter()
+            .map(|p| Point::new(p.x + dx, p.y + dy))
+            .collect();
+        Piece {
             points,
-            shape: self.shape,
+            shape: self.shape.clone(),
             orientation: self.orientation
-        })
+        }
+    }
+
+    // Candidate placements for a rotation, in the order they
+    // should be tried. Defined pieces yield one per kick.
+    pub fn clockwise_rotations(&self) -> Vec<Piece> {
+        rotate::rotations(self, self.orientation.next())
     }
 
-    pub fn project_clockwise_rotation(&self) -> Option<Piece> {
-        rotate::rotate_clockwise(self)
+    pub fn counterclockwise_rotations(&self) -> Vec<Piece> {
+        rotate::rotations(self, self.orientation.prev())
     }
 
-    pub fn project_counterclockwise_rotation(&self) -> Option<Piece> {
-        rotate::rotate_counterclockwise(self)
+    pub fn rotations_180(&self) -> Vec<Piece> {
+        rotate::rotations(self, self.orientation.next().next())
     }
 
     // Return a tuple of points representing the lower left
@@ -257,20 +258,6 @@ impl Piece {
 
         (Point::new(min_x, min_y), Point::new(max_x, max_y))
     }
-
-    fn map_points<F>(&self, f: F) -> Option<[Point; 4]>
-        where F: Fn(&Point) -> Option<Point>
-    {
-        let mut pts: Vec<Point> = vec![];
-        for p in self.points.iter() {
-            pts.push(f(p)?)
-        }
-        let points: [Point; 4] = pts
-            .as_slice()
-            .try_into()
-            .unwrap();
-        Some(points)
-    }
 }
 
 #[cfg(test)]
@@ -332,7 +319,7 @@ mod test {
     #[test]
     fn test_move_left_orange_ricky() {
         let origin = Point::new(6, 19);
-        let piece = Piece::orange_ricky(origin).project_left().unwrap();
+        let piece = Piece::orange_ricky(origin).project_left();
         assert_eq!(piece.points, [
             Point::new(5, 19),
             Point::new(5, 20),
@@ -344,7 +331,7 @@ mod test {
     #[test]
     fn test_move_right_orange_ricky() {
         let origin = Point::new(6, 19);
-        let piece = Piece::orange_ricky(origin).project_right().unwrap();
+        let piece = Piece::orange_ricky(origin).project_right();
         assert_eq!(piece.points, [
             Point::new(7, 19),
             Point::new(7, 20),
@@ -356,7 +343,7 @@ mod test {
     #[test]
     fn test_move_down_orange_ricky() {
         let origin = Point::new(6, 19);
-        let piece = Piece::orange_ricky(origin).project_down().unwrap();
+        let piece = Piece::orange_ricky(origin).project_down();
         assert_eq!(piece.points, [
             Point::new(6, 18),
             Point::new(6, 19),
@@ -368,7 +355,7 @@ mod test {
     #[test]
     fn test_move_down_from_zero_orange_ricky() {
         let origin = Point::new(6, 19);
-        let piece = Piece::orange_ricky(origin).project_down().unwrap();
+        let piece = Piece::orange_ricky(origin).project_down();
         assert_eq!(piece.points, [
             Point::new(6, 18),
             Point::new(6, 19),
@@ -377,6 +364,18 @@ mod test {
         ]);
     }
 
+    #[test]
+    fn test_move_off_board_orange_ricky() {
+        let origin = Point::new(0, 0);
+        let piece = Piece::orange_ricky(origin).project_left().project_down();
+        assert_eq!(piece.points, [
+            Point::new(-1, -1),
+            Point::new(-1, 0),
+            Point::new(0, 0),
+            Point::new(1, 0)
+        ]);
+    }
+
     #[test]
     fn test_points_blue_ricky() {
         let origin = Point::new(6, 19);
@@ -0,0 +1,462 @@
+use std::{
+    collections::HashMap,
+    fmt,
+    fs,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+use rand::{seq::SliceRandom, Rng};
+use serde::{Deserialize, Serialize};
+use super::piece::{Orientation, Piece, Point, Shape};
+use crate::paths;
+
+const TROMINOES: &str = include_str!("../../assets/pieces/trominoes.toml");
+const BIG: &str = include_str!("../../assets/pieces/big.toml");
+const PENTOMINOES: &str = include_str!("../../assets/pieces/pentominoes.toml");
+
+// A cell position relative to the lower left corner of
+// a piece's bounding box, or a kick translation.
+pub type Offset = (i32, i32);
+
+#[derive(Debug)]
+pub enum PieceSetError {
+    Io(PathBuf, std::io::Error),
+    Parse(toml::de::Error),
+    Invalid(String),
+}
+
+impl fmt::Display for PieceSetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PieceSetError::Io(path, err) => write!(f, "{}: {err}", path.display()),
+            PieceSetError::Parse(err) => write!(f, "{err}"),
+            PieceSetError::Invalid(msg) => write!(f, "{msg}"),
+        }
+    }
+}
+
+impl std::error::Error for PieceSetError {}
+
+// A piece described as data: the cells it occupies in each of
+// its four orientations, plus the kicks to try when a rotation
+// doesn't fit where it lands.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct PieceDef {
+    pub name: String,
+    // position within its set
+    pub index: usize,
+    // pieces without a color of their own take one from the level's theme
+    pub color: Option<u8>,
+    pub states: [Vec<Offset>; 4],
+    #[serde(with = "kick_table")]
+    pub kicks: HashMap<(Orientation, Orientation), Vec<Offset>>,
+}
+
+impl PieceDef {
+    pub fn cells(&self, orientation: Orientation) -> &[Offset] {
+        &self.states[orientation.index()]
+    }
+
+    // The translations to attempt, in order, when rotating
+    // between the two orientations.
+    pub fn kicks(&self, from: Orientation, to: Orientation) -> &[Offset] {
+        match self.kicks.get(&(from, to)) {
+            Some(kicks) => kicks,
+            None => &[(0, 0)],
+        }
+    }
+
+    // Place the piece so that it's horizontally centered and its
+    // topmost cell sits on the top row of the board.
+    pub fn spawn(self: &Arc<Self>, board_width: usize, board_height: usize) -> Piece {
+        let cells = self.cells(Orientation::One);
+        let box_width = cells.iter().map(|c| c.0).max().unwrap_or(0) + 1;
+        let top = cells.iter().map(|c| c.1).max().unwrap_or(0);
+        let x = (board_width as i32 - box_width + 1) / 2;
+        let y = board_height as i32 - 1 - top;
+        let points = cells
+            .iter()
+            .map(|(dx, dy)| Point::new(x + dx, y + dy))
+            .collect();
+        Piece {
+            shape: Shape::Defined(Arc::clone(self)),
+            orientation: Orientation::One,
+            points,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Randomizer {
+    // every piece is equally likely on every draw
+    #[default]
+    Random,
+    // deal each piece once, in shuffled order, before repeating any
+    Bag,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PieceSet {
+    pub name: String,
+    pub pieces: Vec<Arc<PieceDef>>,
+    pub randomizer: Randomizer,
+    pub board_width: Option<usize>,
+    pub board_height: Option<usize>,
+    bag: Vec<usize>,
+}
+
+impl PieceSet {
+    pub fn built_in() -> Vec<Self> {
+        [PENTOMINOES, TROMINOES, BIG]
+            .iter()
+            .map(|src| Self::parse(src).expect("built-in piece set is valid"))
+            .collect()
+    }
+
+    // The built-in sets followed by the user's, leaving out
+    // any user files that fail to load
+    pub fn available() -> Vec<Self> {
+        Self::built_in()
+            .into_iter()
+            .chain(Self::load_user_sets().into_iter().flatten())
+            .collect()
+    }
+
+    pub fn load(path: &Path) -> Result<Self, PieceSetError> {
+        let src = fs::read_to_string(path)
+            .map_err(|err| PieceSetError::Io(path.to_path_buf(), err))?;
+        Self::parse(&src)
+    }
+
+    // Every `*.toml` file in the user's piece directory, in name order.
+    pub fn load_user_sets() -> Vec<Result<Self, PieceSetError>> {
+        let Some(dir) = paths::config_dir() else { return vec![] };
+        let dir = dir.join("pieces");
+        let Ok(entries) = fs::read_dir(dir) else { return vec![] };
+        let mut paths: Vec<PathBuf> = entries
+            .flatten()
+            .map(|e| e.path())
+            .filter(|p| p.extension().is_some_and(|ext| ext == "toml"))
+            .collect();
+        paths.sort();
+        paths.iter().map(|p| Self::load(p)).collect()
+    }
+
+    pub fn parse(src: &str) -> Result<Self, PieceSetError> {
+        let raw: RawSet = toml::from_str(src).map_err(PieceSetError::Parse)?;
+        if raw.pieces.is_empty() {
+            return Err(PieceSetError::Invalid(format!("{}: no pieces defined", raw.name)))
+        }
+        let default_kicks = parse_kicks(&raw.kicks)?;
+        let pieces = raw.pieces
+            .iter()
+            .enumerate()
+            .map(|(index, piece)| {
+                let mut kicks = default_kicks.clone();
+                kicks.extend(parse_kicks(&piece.kicks)?);
+                let def = PieceDef {
+                    name: piece.name.clone(),
+                    index,
+                    color: piece.color,
+                    states: parse_states(&piece.name, &piece.states)?,
+                    kicks,
+                };
+                Ok(Arc::new(def))
+            })
+            .collect::<Result<Vec<_>, PieceSetError>>()?;
+
+        Ok(Self {
+            name: raw.name,
+            pieces,
+            randomizer: raw.randomizer,
+            board_width: raw.board_width,
+            board_height: raw.board_height,
+            bag: vec![],
+        })
+    }
+
+    pub fn random<R: Rng>(&mut self, board_width: usize, board_height: usize, rng: &mut R) -> Piece {
+        let i = match self.randomizer {
+            Randomizer::Random => rng.gen_range(0..self.pieces.len()),
+            Randomizer::Bag => {
+                if self.bag.is_empty() {
+                    self.bag = (0..self.pieces.len()).collect();
+                    self.bag.shuffle(rng);
+                }
+                self.bag.pop().unwrap()
+            }
+        };
+        self.pieces[i].spawn(board_width, board_height)
+    }
+}
+
+// Map keys have to be strings in a saved game, so the
+// kick table is written out as a list of entries instead.
+mod kick_table {
+    use std::collections::HashMap;
+    use serde::{Deserialize, Deserializer, Serializer};
+    use super::{Offset, Orientation};
+
+    type Table = HashMap<(Orientation, Orientation), Vec<Offset>>;
+
+    pub fn serialize<S: Serializer>(kicks: &Table, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(kicks.iter())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Table, D::Error> {
+        let entries: Vec<((Orientation, Orientation), Vec<Offset>)> = Vec::deserialize(deserializer)?;
+        Ok(entries.into_iter().collect())
+    }
+}
+
+#[derive(Deserialize)]
+struct RawSet {
+    name: String,
+    #[serde(default)]
+    randomizer: Randomizer,
+    board_width: Option<usize>,
+    board_height: Option<usize>,
+    #[serde(default)]
+    kicks: HashMap<String, Vec<[i32; 2]>>,
+    pieces: Vec<RawPiece>,
+}
+
+#[derive(Deserialize)]
+struct RawPiece {
+    name: String,
+    color: Option<u8>,
+    states: Vec<String>,
+    #[serde(default)]
+    kicks: HashMap<String, Vec<[i32; 2]>>,
+}
+
+// Kick tables are keyed by "<from>><to>" orientation indices, e.g. "0>1"
+// or "0>2" for a half turn, or "*" for any quarter turn without its own
+// entry. Half turns have a table of their own, and don't kick without one.
+fn parse_kicks(
+    raw: &HashMap<String, Vec<[i32; 2]>>
+) -> Result<HashMap<(Orientation, Orientation), Vec<Offset>>, PieceSetError> {
+    let mut kicks = HashMap::new();
+    if let Some(offsets) = raw.get("*") {
+        for from in Orientation::all() {
+            for to in [from.next(), from.prev()] {
+                kicks.insert((from, to), to_offsets(offsets));
+            }
+        }
+    }
+    for (key, offsets) in raw.iter().filter(|(key, _)| *key != "*") {
+        let invalid = || PieceSetError::Invalid(format!("invalid kick key \"{key}\", expected e.g. \"0>1\""));
+        let (from, to) = key.split_once('>').ok_or_else(invalid)?;
+        let from = parse_orientation(from).ok_or_else(invalid)?;
+        let to = parse_orientation(to).ok_or_else(invalid)?;
+        kicks.insert((from, to), to_offsets(offsets));
+    }
+    Ok(kicks)
+}
+
+fn to_offsets(raw: &[[i32; 2]]) -> Vec<Offset> {
+    raw.iter().map(|[x, y]| (*x, *y)).collect()
+}
+
+fn parse_orientation(s: &str) -> Option<Orientation> {
+    let i: usize = s.trim().parse().ok()?;
+    Orientation::all().get(i).copied()
+}
+
+// Each state is drawn as rows of `#` (filled) and `.` (empty), top row first.
+// A single state may be given, in which case the other three are derived by
+// rotating it clockwise within its (square) bounding box.
+fn parse_states(name: &str, raw: &[String]) -> Result<[Vec<Offset>; 4], PieceSetError> {
+    let grids = raw
+        .iter()
+        .map(|s| parse_grid(name, s))
+        .collect::<Result<Vec<_>, _>>()?;
+    let grids = match grids.len() {
+        1 => {
+            let first = &grids[0];
+            if first.iter().any(|row| row.len() != first.len()) {
+                return Err(PieceSetError::Invalid(
+                    format!("{name}: a single state must be drawn in a square box")
+                ))
+            }
+            let second = rotate_grid(first);
+            let third = rotate_grid(&second);
+            let fourth = rotate_grid(&third);
+            vec![first.clone(), second, third, fourth]
+        },
+        4 => grids,
+        n => return Err(PieceSetError::Invalid(
+            format!("{name}: expected 1 or 4 states, found {n}")
+        ))
+    };
+
+    let states: Vec<Vec<Offset>> = grids.iter().map(|g| grid_cells(g)).collect();
+    let size = states[0].len();
+    if size == 0 || states.iter().any(|s| s.len() != size) {
+        return Err(PieceSetError::Invalid(
+            format!("{name}: every state must have the same, non-zero number of cells")
+        ))
+    }
+    Ok(states.try_into().unwrap())
+}
+
+fn parse_grid(name: &str, raw: &str) -> Result<Vec<Vec<bool>>, PieceSetError> {
+    let grid: Vec<Vec<bool>> = raw
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            line.chars()
+                .map(|c| match c {
+                    '#' => Ok(true),
+                    '.' => Ok(false),
+                    other => Err(PieceSetError::Invalid(
+                        format!("{name}: unexpected '{other}' in state, use '#' or '.'")
+                    )),
+                })
+                .collect()
+        })
+        .collect::<Result<_, _>>()?;
+    if grid.is_empty() || grid.iter().any(|row| row.len() != grid[0].len()) {
+        return Err(PieceSetError::Invalid(
+            format!("{name}: state rows must all be the same width")
+        ))
+    }
+    Ok(grid)
+}
+
+fn rotate_grid(grid: &[Vec<bool>]) -> Vec<Vec<bool>> {
+    let n = grid.len();
+    (0..n)
+        .map(|r| (0..n).map(|c| grid[n - 1 - c][r]).collect())
+        .collect()
+}
+
+// Convert a top-row-first grid into offsets from its lower left corner,
+// ordered bottom row first and left to right within a row.
+fn grid_cells(grid: &[Vec<bool>]) -> Vec<Offset> {
+    grid.iter()
+        .rev()
+        .enumerate()
+        .flat_map(|(y, row)| {
+            row.iter()
+                .enumerate()
+                .filter(|(_, filled)| **filled)
+                .map(move |(x, _)| (x as i32, y as i32))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const L_TROMINO: &str = r#"
+        name = "Test"
+
+        [kicks]
+        "*" = [[0, 0], [-1, 0]]
+
+        [[pieces]]
+        name = "L"
+        color = 3
+        states = ["""
+            #.
+            ##
+        """]
+    "#;
+
+    #[test]
+    fn test_parse_derives_rotations() {
+        let set = PieceSet::parse(L_TROMINO).unwrap();
+        let def = &set.pieces[0];
+        assert_eq!(def.cells(Orientation::One), &[(0, 0), (1, 0), (0, 1)]);
+        assert_eq!(def.cells(Orientation::Two), &[(0, 0), (0, 1), (1, 1)]);
+        assert_eq!(def.cells(Orientation::Three), &[(1, 0), (0, 1), (1, 1)]);
+        assert_eq!(def.cells(Orientation::Four), &[(0, 0), (1, 0), (1, 1)]);
+    }
+
+    #[test]
+    fn test_parse_kicks() {
+        let set = PieceSet::parse(L_TROMINO).unwrap();
+        let def = &set.pieces[0];
+        assert_eq!(def.kicks(Orientation::One, Orientation::Two), &[(0, 0), (-1, 0)]);
+        assert_eq!(def.kicks(Orientation::One, Orientation::Three), &[(0, 0)]);
+    }
+
+    #[test]
+    fn test_parse_half_turn_kicks() {
+        let src = L_TROMINO.replace("[kicks]", "[kicks]\n\"0>2\" = [[0, 0], [0, -1]]");
+        let set = PieceSet::parse(&src).unwrap();
+        let def = &set.pieces[0];
+        assert_eq!(def.kicks(Orientation::One, Orientation::Three), &[(0, 0), (0, -1)]);
+        assert_eq!(def.kicks(Orientation::Two, Orientation::Four), &[(0, 0)]);
+        assert_eq!(def.kicks(Orientation::One, Orientation::Two), &[(0, 0), (-1, 0)]);
+    }
+
+    #[test]
+    fn test_parse_rejects_mismatched_states() {
+        let src = r###"
+            name = "Bad"
+            [[pieces]]
+            name = "X"
+            color = 1
+            states = ["#.", "##.", "#.", "##"]
+        "###;
+        assert!(matches!(PieceSet::parse(src), Err(PieceSetError::Invalid(_))));
+    }
+
+    #[test]
+    fn test_built_in_sets_are_valid() {
+        let sets = PieceSet::built_in();
+        assert_eq!(sets.len(), 3);
+        assert_eq!(sets[0].pieces.len(), 18);
+        assert!(sets[0].pieces.iter().all(|def| def.cells(Orientation::One).len() == 5));
+    }
+
+    #[test]
+    fn test_bag_deals_every_piece() {
+        let mut set = PieceSet::built_in().remove(0);
+        let mut rng = rand::thread_rng();
+        assert_eq!(set.randomizer, Randomizer::Bag);
+        let mut dealt: Vec<usize> = (0..18)
+            .map(|_| match set.random(12, 20, &mut rng).shape {
+                Shape::Defined(def) => def.index,
+                _ => unreachable!(),
+            })
+            .collect();
+        dealt.sort();
+        assert_eq!(dealt, (0..18).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_spawn_at_top_center() {
+        let set = PieceSet::parse(L_TROMINO).unwrap();
+        let piece = set.pieces[0].spawn(10, 20);
+        assert_eq!(piece.points, [
+            Point::new(4, 18),
+            Point::new(5, 18),
+            Point::new(4, 19),
+        ]);
+    }
+
+    #[test]
+    fn test_rotate_with_kick() {
+        let set = PieceSet::parse(L_TROMINO).unwrap();
+        let piece = set.pieces[0].spawn(10, 20);
+        let rotations = piece.clockwise_rotations();
+        assert_eq!(rotations.len(), 2);
+        assert_eq!(rotations[0].orientation, Orientation::Two);
+        assert_eq!(rotations[0].points, [
+            Point::new(4, 18),
+            Point::new(4, 19),
+            Point::new(5, 19),
+        ]);
+        assert_eq!(rotations[1].points, [
+            Point::new(3, 18),
+            Point::new(3, 19),
+            Point::new(4, 19),
+        ]);
+    }
+}
@@ -1,171 +1,126 @@
-use crate::game::{
-    matrix::Matrix,
-    piece::{Piece, Point, Shape, Orientation}
-};
-
-// From a given piece, return a new piece containing
-// the points after rotation.
-pub fn rotate_clockwise(piece: &Piece) -> Option<Piece> {
-    let bbox = generate_bounding_matrix(piece);
-    let mut transposed = transpose(bbox);
-    reverse_rows(&mut transposed);
-
-    let (x_offset, y_offset) = derive_offsets_for_cw(piece)?;
-    let points = extract_points_from_bounding_matrix(
-        &transposed,
-        x_offset,
-        y_offset
-    );
-
-    Some(Piece {
-        shape: piece.shape,
-        points,
-        orientation: piece.orientation.next()
-    })
-}
-
-pub fn rotate_counterclockwise(piece: &Piece) -> Option<Piece> {
-    let mut bbox = generate_bounding_matrix(piece);
-    reverse_rows(&mut bbox);
-    let transposed = transpose(bbox);
-
-    let (x_offset, y_offset) = derive_offsets_for_ccw(piece)?;
-    let points = extract_points_from_bounding_matrix(
-        &transposed,
-        x_offset,
-        y_offset
-    );
-
-    Some(Piece {
-        shape: piece.shape,
-        points,
-        orientation: piece.orientation.prev()
-    })
+use crate::game::piece::{Piece, Point, Shape, Orientation};
+use crate::game::piece_set::Offset;
+
+// Cells of each classic shape in its four orientations, relative
+// to a pivot that stays put as the piece turns. A piece occupies
+// `pivot + cell` for each cell of its current orientation, so a
+// rotation is a table lookup rather than a matrix transform.
+// Cells are listed bottom row first, left to right.
+type Table = [[Offset; 4]; 4];
+
+const ORANGE_RICKY: Table = [
+    [(0, 0), (0, 1), (1, 1), (2, 1)],
+    [(1, 0), (1, 1), (0, 2), (1, 2)],
+    [(0, 1), (1, 1), (2, 1), (2, 2)],
+    [(1, 0), (2, 0), (1, 1), (1, 2)],
+];
+
+const BLUE_RICKY: Table = [
+    [(2, 0), (0, 1), (1, 1), (2, 1)],
+    [(0, 0), (1, 0), (1, 1), (1, 2)],
+    [(0, 1), (1, 1), (2, 1), (0, 2)],
+    [(1, 0), (1, 1), (1, 2), (2, 2)],
+];
+
+const CLEVELAND_Z: Table = [
+    [(1, 0), (2, 0), (0, 1), (1, 1)],
+    [(1, 0), (1, 1), (2, 1), (2, 2)],
+    [(1, 0), (2, 0), (0, 1), (1, 1)],
+    [(1, 0), (1, 1), (2, 1), (2, 2)],
+];
+
+const RHODE_ISLAND_Z: Table = [
+    [(0, 0), (1, 0), (1, 1), (2, 1)],
+    [(2, 0), (1, 1), (2, 1), (1, 2)],
+    [(0, 0), (1, 0), (1, 1), (2, 1)],
+    [(2, 0), (1, 1), (2, 1), (1, 2)],
+];
+
+const HERO: Table = [
+    [(0, 0), (1, 0), (2, 0), (3, 0)],
+    [(2, -1), (2, 0), (2, 1), (2, 2)],
+    [(0, 0), (1, 0), (2, 0), (3, 0)],
+    [(2, -1), (2, 0), (2, 1), (2, 2)],
+];
+
+const TEEWEE: Table = [
+    [(1, 0), (0, 1), (1, 1), (2, 1)],
+    [(1, 0), (0, 1), (1, 1), (1, 2)],
+    [(0, 1), (1, 1), (2, 1), (1, 2)],
+    [(1, 0), (1, 1), (2, 1), (1, 2)],
+];
+
+const SMASHBOY: Table = [
+    [(0, 0), (1, 0), (0, 1), (1, 1)],
+    [(0, 0), (1, 0), (0, 1), (1, 1)],
+    [(0, 0), (1, 0), (0, 1), (1, 1)],
+    [(0, 0), (1, 0), (0, 1), (1, 1)],
+];
+
+fn cells(shape: &Shape, orientation: Orientation) -> &[Offset] {
+    let table = match shape {
+        Shape::OrangeRicky => &ORANGE_RICKY,
+        Shape::BlueRicky => &BLUE_RICKY,
+        Shape::ClevelandZ => &CLEVELAND_Z,
+        Shape::RhodeIslandZ => &RHODE_ISLAND_Z,
+        Shape::Hero => &HERO,
+        Shape::Teewee => &TEEWEE,
+        Shape::Smashboy => &SMASHBOY,
+        Shape::Defined(def) => return def.cells(orientation),
+    };
+    &table[orientation.index()]
 }
 
-fn reverse_rows(matrix: &mut Matrix<usize>) {
-    matrix
-        .rows
-        .iter_mut()
-        .for_each(|row| row.reverse());
+// The classic shapes don't kick
+fn kicks(shape: &Shape, from: Orientation, to: Orientation) -> &[Offset] {
+    match shape {
+        Shape::Defined(def) => def.kicks(from, to),
+        _ => &[(0, 0)],
+    }
 }
 
-fn generate_bounding_matrix(piece: &Piece) -> Matrix<usize> {
-    let (lower_left, upper_right) = piece.bounds();
-    let width = upper_right.x - lower_left.x + 1;
-    let height = upper_right.y - lower_left.y + 1;
-    let mut out = Matrix::new(width, height, 0);
-    piece
-        .points
+// Candidate placements for turning the piece to the given orientation,
+// one per kick, in the order they should be tried.
+pub fn rotations(piece: &Piece, to: Orientation) -> Vec<Piece> {
+    kicks(&piece.shape, piece.orientation, to)
         .iter()
-        .enumerate()
-        .for_each(|(i, p)| {
-            let x = p.x - lower_left.x;
-            let y = p.y - lower_left.y;
-            out.set(x, y, i + 1);
-        });
-    out
+        .map(|kick| place(piece, to, *kick))
+        .collect()
 }
 
-fn transpose(matrix: Matrix<usize>) -> Matrix<usize> {
-    let height = matrix.height();
-    let width = matrix.width();
-    let mut out = Matrix::new(height, width, 0);
-
-    for y in 0..height {
-        for x in 0..width {
-            let val = matrix.get(x, y).unwrap();
-            let new_x = (width - 1) - x; // return to zero-based
-            let new_y = (height - 1) - y;
-            out.set(new_y, new_x, val);
-        }
-    }
-    out
+// Plain turns with no kicks. The game goes through `rotations`,
+// these check the tables turn the right way round.
+#[cfg(test)]
+fn rotate_clockwise(piece: &Piece) -> Piece {
+    place(piece, piece.orientation.next(), (0, 0))
 }
 
-fn extract_points_from_bounding_matrix(
-    bbox: &Matrix<usize>,
-    x_offset: usize,
-    y_offset: usize
-) -> [Point; 4] {
-    let mut points: Vec<Point> = vec![];
-    for (y, row) in bbox.rows.iter().enumerate() {
-        for (x, i) in row.iter().enumerate() {
-            if i > &0 {
-                let new_x = x + x_offset;
-                let new_y = y + y_offset;
-                points.push(Point::new(new_x, new_y));
-            }
-        }
-    }
-    points
-        .as_slice()
-        .try_into()
-        .unwrap()
+#[cfg(test)]
+fn rotate_counterclockwise(piece: &Piece) -> Piece {
+    place(piece, piece.orientation.prev(), (0, 0))
 }
 
-// returns x and y offsets relative to the bounding matrix
-// based on piece shape and position.
-// orientation values here are the mid-transition "previous state"
-// - prior to being reassigned
-fn derive_offsets_for_cw(piece: &Piece) -> Option<(usize, usize)> {
-    let (lower_left, _) = piece.bounds();
-    let offsets = match (piece.shape, piece.orientation) {
-        (Shape::OrangeRicky, Orientation::Two) => (lower_left.x, lower_left.y + 1),
-        (Shape::OrangeRicky, Orientation::Three) => (lower_left.x + 1, lower_left.y.checked_sub(1)?),
-        (Shape::OrangeRicky, Orientation::Four) => (lower_left.x.checked_sub(1)?, lower_left.y),
-        (Shape::BlueRicky, Orientation::Two) => (lower_left.x, lower_left.y + 1),
-        (Shape::BlueRicky, Orientation::Three) => (lower_left.x + 1, lower_left.y.checked_sub(1)?),
-        (Shape::BlueRicky, Orientation::Four) => (lower_left.x.checked_sub(1)?, lower_left.y),
-        (Shape::Teewee, Orientation::Two) => (lower_left.x, lower_left.y + 1),
-        (Shape::Teewee, Orientation::Three) => (lower_left.x + 1, lower_left.y.checked_sub(1)?),
-        (Shape::Teewee, Orientation::Four) => (lower_left.x.checked_sub(1)?, lower_left.y),
-        (Shape::ClevelandZ, Orientation::One) => (lower_left.x + 1, lower_left.y),
-        (Shape::ClevelandZ, Orientation::Two) => (lower_left.x.checked_sub(1)?, lower_left.y),
-        (Shape::ClevelandZ, Orientation::Three) => (lower_left.x + 1, lower_left.y),
-        (Shape::ClevelandZ, Orientation::Four) => (lower_left.x.checked_sub(1)?, lower_left.y),
-        (Shape::RhodeIslandZ, Orientation::One) => (lower_left.x + 1, lower_left.y),
-        (Shape::RhodeIslandZ, Orientation::Two) => (lower_left.x.checked_sub(1)?, lower_left.y),
-        (Shape::RhodeIslandZ, Orientation::Three) => (lower_left.x + 1, lower_left.y),
-        (Shape::RhodeIslandZ, Orientation::Four) => (lower_left.x.checked_sub(1)?, lower_left.y),
-        (Shape::Hero, Orientation::One) => (lower_left.x + 2, lower_left.y.checked_sub(1)?),
-        (Shape::Hero, Orientation::Two) => (lower_left.x.checked_sub(2)?, lower_left.y + 1),
-        (Shape::Hero, Orientation::Three) => (lower_left.x + 2, lower_left.y.checked_sub(1)?),
-        (Shape::Hero, Orientation::Four) => (lower_left.x.checked_sub(2)?, lower_left.y + 1),
-        _ => (lower_left.x, lower_left.y)
-    };
-    Some(offsets)
+#[cfg(test)]
+fn rotate_180(piece: &Piece) -> Piece {
+    place(piece, piece.orientation.next().next(), (0, 0))
 }
 
-// orientation values here are the mid-transition, "previous state"
-// - prior to being reassigned
-fn derive_offsets_for_ccw(piece: &Piece) -> Option<(usize, usize)> {
-    let (lower_left, _) = piece.bounds();
-    let offsets = match (piece.shape, piece.orientation) {
-        (Shape::OrangeRicky, Orientation::One) => (lower_left.x + 1, lower_left.y),
-        (Shape::OrangeRicky, Orientation::Three) => (lower_left.x, lower_left.y.checked_sub(1)?),
-        (Shape::OrangeRicky, Orientation::Four) => (lower_left.x.checked_sub(1)?, lower_left.y + 1),
-        (Shape::BlueRicky, Orientation::One) => (lower_left.x + 1, lower_left.y),
-        (Shape::BlueRicky, Orientation::Three) => (lower_left.x, lower_left.y.checked_sub(1)?),
-        (Shape::BlueRicky, Orientation::Four) => (lower_left.x.checked_sub(1)?, lower_left.y + 1),
-        (Shape::Teewee, Orientation::One) => (lower_left.x + 1, lower_left.y),
-        (Shape::Teewee, Orientation::Three) => (lower_left.x, lower_left.y.checked_sub(1)?),
-        (Shape::Teewee, Orientation::Four) => (lower_left.x.checked_sub(1)?, lower_left.y + 1),
-        (Shape::ClevelandZ, Orientation::One) => (lower_left.x + 1, lower_left.y),
-        (Shape::ClevelandZ, Orientation::Two) => (lower_left.x.checked_sub(1)?, lower_left.y),
-        (Shape::ClevelandZ, Orientation::Three) => (lower_left.x + 1, lower_left.y),
-        (Shape::ClevelandZ, Orientation::Four) => (lower_left.x.checked_sub(1)?, lower_left.y),
-        (Shape::RhodeIslandZ, Orientation::One) => (lower_left.x + 1, lower_left.y),
-        (Shape::RhodeIslandZ, Orientation::Two) => (lower_left.x.checked_sub(1)?, lower_left.y),
-        (Shape::RhodeIslandZ, Orientation::Three) => (lower_left.x + 1, lower_left.y),
-        (Shape::RhodeIslandZ, Orientation::Four) => (lower_left.x.checked_sub(1)?, lower_left.y),
-        (Shape::Hero, Orientation::One) => (lower_left.x + 2, lower_left.y.checked_sub(1)?),
-        (Shape::Hero, Orientation::Two) => (lower_left.x.checked_sub(2)?, lower_left.y + 1),
-        (Shape::Hero, Orientation::Three) => (lower_left.x + 2, lower_left.y.checked_sub(1)?),
-        (Shape::Hero, Orientation::Four) => (lower_left.x.checked_sub(2)?, lower_left.y + 1),
-        _ => (lower_left.x, lower_left.y)
-    };
-    Some(offsets)
+// The pivot is recovered from the piece's first point, which always
+// corresponds to the first cell of its current orientation.
+fn place(piece: &Piece, to: Orientation, (kick_x, kick_y): Offset) -> Piece {
+    let (from_x, from_y) = cells(&piece.shape, piece.orientation)[0];
+    let pivot_x = piece.points[0].x - from_x;
+    let pivot_y = piece.points[0].y - from_y;
+    let points = cells(&piece.shape, to)
+        .iter()
+        .map(|(x, y)| Point::new(pivot_x + x + kick_x, pivot_y + y + kick_y))
+        .collect();
+
+    Piece {
+        shape: piece.shape.clone(),
+        points,
+        orientation: to
+    }
 }
 
 #[cfg(test)]
@@ -176,7 +131,7 @@ mod test {
     fn test_rotate_clockwise_smashboy() {
         let origin = Point::new(6, 10);
         let piece = Piece::smashboy(origin);
-        let r90 = rotate_clockwise(&piece).unwrap();
+        let r90 = rotate_clockwise(&piece);
         assert_eq!(r90.orientation, Orientation::Two);
         assert_eq!(r90.points, [
             Point::new(6, 10),
@@ -185,7 +140,7 @@ mod test {
             Point::new(7, 11)
         ]);
 
-        let r180 = rotate_clockwise(&r90).unwrap();
+        let r180 = rotate_clockwise(&r90);
         assert_eq!(r180.orientation, Orientation::Three);
         assert_eq!(r180.points, [
             Point::new(6, 10),
@@ -194,7 +149,7 @@ mod test {
             Point::new(7, 11)
         ]);
 
-        let r270 = rotate_clockwise(&r180).unwrap();
+        let r270 = rotate_clockwise(&r180);
         assert_eq!(r270.orientation, Orientation::Four);
         assert_eq!(r270.points, [
             Point::new(6, 10),
@@ -203,7 +158,7 @@ mod test {
             Point::new(7, 11)
         ]);
 
-        let r360 = rotate_clockwise(&r270).unwrap();
+        let r360 = rotate_clockwise(&r270);
         assert_eq!(r360.orientation, Orientation::One);
         assert_eq!(r360.points, [
             Point::new(6, 10),
@@ -217,7 +172,7 @@ mod test {
     fn test_rotate_counterclockwise_smashboy() {
         let origin = Point::new(6, 10);
         let piece = Piece::smashboy(origin);
-        let r90 = rotate_counterclockwise(&piece).unwrap();
+        let r90 = rotate_counterclockwise(&piece);
         assert_eq!(r90.orientation, Orientation::Four);
         assert_eq!(r90.points, [
             Point::new(6, 10),
@@ -226,7 +181,7 @@ mod test {
             Point::new(7, 11)
         ]);
 
-        let r180 = rotate_counterclockwise(&r90).unwrap();
+        let r180 = rotate_counterclockwise(&r90);
         assert_eq!(r180.orientation, Orientation::Three);
         assert_eq!(r180.points, [
             Point::new(6, 10),
@@ -235,7 +190,7 @@ mod test {
             Point::new(7, 11)
         ]);
 
-        let r270 = rotate_counterclockwise(&r180).unwrap();
+        let r270 = rotate_counterclockwise(&r180);
         assert_eq!(r270.orientation, Orientation::Two);
         assert_eq!(r270.points, [
             Point::new(6, 10),
@@ -244,7 +199,7 @@ mod test {
             Point::new(7, 11)
         ]);
 
-        let r360 = rotate_counterclockwise(&r270).unwrap();
+        let r360 = rotate_counterclockwise(&r270);
         assert_eq!(r360.orientation, Orientation::One);
         assert_eq!(r360.points, [
             Point::new(6, 10),
@@ -258,7 +213,7 @@ mod test {
     fn test_rotate_clockwise_hero() {
         let origin = Point::new(6, 10);
         let piece = Piece::hero(origin);
-        let r90 = rotate_clockwise(&piece).unwrap();
+        let r90 = rotate_clockwise(&piece);
         assert_eq!(r90.orientation, Orientation::Two);
         assert_eq!(r90.points, [
             Point::new(8, 9),
@@ -267,7 +222,7 @@ mod test {
             Point::new(8, 12)
         ]);
 
-        let r180 = rotate_clockwise(&r90).unwrap();
+        let r180 = rotate_clockwise(&r90);
         assert_eq!(r180.orientation, Orientation::Three);
         assert_eq!(r180.points, [
             Point::new(6, 10),
@@ -276,7 +231,7 @@ mod test {
             Point::new(9, 10)
         ]);
 
-        let r270 = rotate_clockwise(&r180).unwrap();
+        let r270 = rotate_clockwise(&r180);
         assert_eq!(r270.orientation, Orientation::Four);
         assert_eq!(r270.points, [
             Point::new(8, 9),
@@ -285,7 +240,7 @@ mod test {
             Point::new(8, 12)
         ]);
 
-        let r360 = rotate_clockwise(&r270).unwrap();
+        let r360 = rotate_clockwise(&r270);
         assert_eq!(r360.orientation, Orientation::One);
         assert_eq!(r360.points, [
             Point::new(6, 10),
@@ -299,7 +254,7 @@ mod test {
     fn test_rotate_counterclockwise_hero() {
         let origin = Point::new(6, 10);
         let piece = Piece::hero(origin);
-        let r90 = rotate_counterclockwise(&piece).unwrap();
+        let r90 = rotate_counterclockwise(&piece);
         assert_eq!(r90.orientation, Orientation::Four);
         assert_eq!(r90.points, [
             Point::new(8, 9),
@@ -308,7 +263,7 @@ mod test {
             Point::new(8, 12)
         ]);
 
-        let r180 = rotate_counterclockwise(&r90).unwrap();
+        let r180 = rotate_counterclockwise(&r90);
         assert_eq!(r180.orientation, Orientation::Three);
         assert_eq!(r180.points, [
             Point::new(6, 10),
@@ -317,7 +272,7 @@ mod test {
             Point::new(9, 10)
         ]);
 
-        let r270 = rotate_counterclockwise(&r180).unwrap();
+        let r270 = rotate_counterclockwise(&r180);
         assert_eq!(r270.orientation, Orientation::Two);
         assert_eq!(r270.points, [
             Point::new(8, 9),
@@ -326,7 +281,7 @@ mod test {
             Point::new(8, 12)
         ]);
 
-        let r360 = rotate_counterclockwise(&r270).unwrap();
+        let r360 = rotate_counterclockwise(&r270);
         assert_eq!(r360.orientation, Orientation::One);
         assert_eq!(r360.points, [
             Point::new(6, 10),
@@ -340,7 +295,7 @@ mod test {
     fn test_rotate_clockwise_rhode_island_z() {
         let origin = Point::new(6, 10);
         let piece = Piece::rhode_island_z(origin);
-        let r90 = rotate_clockwise(&piece).unwrap();
+        let r90 = rotate_clockwise(&piece);
         assert_eq!(r90.orientation, Orientation::Two);
         assert_eq!(r90.points, [
             Point::new(8, 10),
@@ -349,7 +304,7 @@ mod test {
             Point::new(7, 12)
         ]);
 
-        let r180 = rotate_clockwise(&r90).unwrap();
+        let r180 = rotate_clockwise(&r90);
         assert_eq!(r180.orientation, Orientation::Three);
         assert_eq!(r180.points, [
             Point::new(6, 10),
@@ -358,7 +313,7 @@ mod test {
             Point::new(8, 11),
         ]);
 
-        let r270 = rotate_clockwise(&r180).unwrap();
+        let r270 = rotate_clockwise(&r180);
         assert_eq!(r270.orientation, Orientation::Four);
         assert_eq!(r270.points, [
             Point::new(8, 10),
@@ -367,7 +322,7 @@ mod test {
             Point::new(7, 12)
         ]);
 
-        let r360 = rotate_clockwise(&r270).unwrap();
+        let r360 = rotate_clockwise(&r270);
         assert_eq!(r360.orientation, Orientation::One);
         assert_eq!(r360.points, [
             Point::new(6, 10),
@@ -381,7 +336,7 @@ mod test {
     fn test_rotate_counterclockwise_rhode_island_z() {
         let origin = Point::new(6, 10);
         let piece = Piece::rhode_island_z(origin);
-        let r90 = rotate_counterclockwise(&piece).unwrap();
+        let r90 = rotate_counterclockwise(&piece);
         assert_eq!(r90.orientation, Orientation::Four);
         assert_eq!(r90.points, [
             Point::new(8, 10),
@@ -390,7 +345,7 @@ mod test {
             Point::new(7, 12)
         ]);
 
-        let r180 = rotate_counterclockwise(&r90).unwrap();
+        let r180 = rotate_counterclockwise(&r90);
         assert_eq!(r180.orientation, Orientation::Three);
         assert_eq!(r180.points, [
             Point::new(6, 10),
@@ -399,7 +354,7 @@ mod test {
             Point::new(8, 11),
         ]);
 
-        let r270 = rotate_counterclockwise(&r180).unwrap();
+        let r270 = rotate_counterclockwise(&r180);
         assert_eq!(r270.orientation, Orientation::Two);
         assert_eq!(r270.points, [
             Point::new(8, 10),
@@ -408,7 +363,7 @@ mod test {
             Point::new(7, 12)
         ]);
 
-        let r360 = rotate_counterclockwise(&r270).unwrap();
+        let r360 = rotate_counterclockwise(&r270);
         assert_eq!(r360.orientation, Orientation::One);
         assert_eq!(r360.points, [
             Point::new(6, 10),
@@ -422,7 +377,7 @@ mod test {
     fn test_rotate_clockwise_cleveland_z() {
         let origin = Point::new(6, 10);
         let piece = Piece::cleveland_z(origin);
-        let r90 = rotate_clockwise(&piece).unwrap();
+        let r90 = rotate_clockwise(&piece);
         assert_eq!(r90.orientation, Orientation::Two);
         assert_eq!(r90.points, [
             Point::new(7, 10),
@@ -431,7 +386,7 @@ mod test {
             Point::new(8, 12)
         ]);
 
-        let r180 = rotate_clockwise(&r90).unwrap();
+        let r180 = rotate_clockwise(&r90);
         assert_eq!(r180.orientation, Orientation::Three);
         assert_eq!(r180.points, [
             Point::new(7, 10),
@@ -440,7 +395,7 @@ mod test {
             Point::new(7, 11)
         ]);
 
-        let r270 = rotate_clockwise(&r180).unwrap();
+        let r270 = rotate_clockwise(&r180);
         assert_eq!(r270.orientation, Orientation::Four);
         assert_eq!(r270.points, [
             Point::new(7, 10),
@@ -449,7 +404,7 @@ mod test {
             Point::new(8, 12)
         ]);
 
-        let r360 = rotate_clockwise(&r270).unwrap();
+        let r360 = rotate_clockwise(&r270);
         assert_eq!(r360.orientation, Orientation::One);
         assert_eq!(r360.points, [
             Point::new(7, 10),
@@ -463,7 +418,7 @@ mod test {
     fn test_rotate_counterclockwise_cleveland_z() {
         let origin = Point::new(6, 10);
         let piece = Piece::cleveland_z(origin);
-        let r90 = rotate_counterclockwise(&piece).unwrap();
+        let r90 = rotate_counterclockwise(&piece);
         assert_eq!(r90.orientation, Orientation::Four);
         assert_eq!(r90.points, [
             Point::new(7, 10),
@@ -472,7 +427,7 @@ mod test {
             Point::new(8, 12)
         ]);
 
-        let r180 = rotate_counterclockwise(&r90).unwrap();
+        let r180 = rotate_counterclockwise(&r90);
         assert_eq!(r180.orientation, Orientation::Three);
         assert_eq!(r180.points, [
             Point::new(7, 10),
@@ -481,7 +436,7 @@ mod test {
             Point::new(7, 11)
         ]);
 
-        let r270 = rotate_counterclockwise(&r180).unwrap();
+        let r270 = rotate_counterclockwise(&r180);
         assert_eq!(r270.orientation, Orientation::Two);
         assert_eq!(r270.points, [
             Point::new(7, 10),
@@ -490,7 +445,7 @@ mod test {
             Point::new(8, 12)
         ]);
 
-        let r360 = rotate_counterclockwise(&r270).unwrap();
+        let r360 = rotate_counterclockwise(&r270);
         assert_eq!(r360.orientation, Orientation::One);
         assert_eq!(r360.points, [
             Point::new(7, 10),
@@ -504,7 +459,7 @@ mod test {
     fn test_rotate_clockwise_teewee() {
         let origin = Point::new(6, 10);
         let piece = Piece::teewee(origin);
-        let r90 = rotate_clockwise(&piece).unwrap();
+        let r90 = rotate_clockwise(&piece);
         assert_eq!(r90.orientation, Orientation::Two);
         assert_eq!(r90.points, [
             Point::new(7, 10),
@@ -513,7 +468,7 @@ mod test {
             Point::new(7, 12)
         ]);
 
-        let r180 = rotate_clockwise(&r90).unwrap();
+        let r180 = rotate_clockwise(&r90);
         assert_eq!(r180.orientation, Orientation::Three);
         assert_eq!(r180.points, [
             Point::new(6, 11),
@@ -522,7 +477,7 @@ mod test {
             Point::new(7, 12)
         ]);
 
-        let r270 = rotate_clockwise(&r180).unwrap();
+        let r270 = rotate_clockwise(&r180);
         assert_eq!(r270.orientation, Orientation::Four);
         assert_eq!(r270.points, [
             Point::new(7, 10),
@@ -531,7 +486,7 @@ mod test {
             Point::new(7, 12)
         ]);
 
-        let r360 = rotate_clockwise(&r270).unwrap();
+        let r360 = rotate_clockwise(&r270);
         assert_eq!(r360.orientation, Orientation::One);
         assert_eq!(r360.points, [
             Point::new(7, 10),
@@ -545,7 +500,7 @@ mod test {
     fn test_rotate_counterclockwise_teewee() {
         let origin = Point::new(6, 10);
         let piece = Piece::teewee(origin);
-        let r90 = rotate_counterclockwise(&piece).unwrap();
+        let r90 = rotate_counterclockwise(&piece);
         assert_eq!(r90.orientation, Orientation::Four);
         assert_eq!(r90.points, [
             Point::new(7, 10),
@@ -554,7 +509,7 @@ mod test {
             Point::new(7, 12)
         ]);
 
-        let r180 = rotate_counterclockwise(&r90).unwrap();
+        let r180 = rotate_counterclockwise(&r90);
         assert_eq!(r180.orientation, Orientation::Three);
         assert_eq!(r180.points, [
             Point::new(6, 11),
@@ -563,7 +518,7 @@ mod test {
             Point::new(7, 12)
         ]);
 
-        let r270 = rotate_counterclockwise(&r180).unwrap();
+        let r270 = rotate_counterclockwise(&r180);
         assert_eq!(r270.orientation, Orientation::Two);
         assert_eq!(r270.points, [
             Point::new(7, 10),
@@ -572,7 +527,7 @@ mod test {
             Point::new(7, 12)
         ]);
 
-        let r360 = rotate_counterclockwise(&r270).unwrap();
+        let r360 = rotate_counterclockwise(&r270);
         assert_eq!(r360.orientation, Orientation::One);
         assert_eq!(r360.points, [
             Point::new(7, 10),
@@ -586,7 +541,7 @@ mod test {
     fn test_rotate_clockwise_blue_ricky() {
         let origin = Point::new(6, 10);
         let piece = Piece::blue_ricky(origin);
-        let r90 = rotate_clockwise(&piece).unwrap();
+        let r90 = rotate_clockwise(&piece);
         assert_eq!(r90.orientation, Orientation::Two);
         assert_eq!(r90.points, [
             Point::new(6, 10),
@@ -595,7 +550,7 @@ mod test {
             Point::new(7, 12)
         ]);
 
-        let r180 = rotate_clockwise(&r90).unwrap();
+        let r180 = rotate_clockwise(&r90);
         assert_eq!(r180.orientation, Orientation::Three);
         assert_eq!(r180.points, [
             Point::new(6, 11),
@@ -604,7 +559,7 @@ mod test {
             Point::new(6, 12)
         ]);
 
-        let r270 = rotate_clockwise(&r180).unwrap();
+        let r270 = rotate_clockwise(&r180);
         assert_eq!(r270.orientation, Orientation::Four);
         assert_eq!(r270.points, [
             Point::new(7, 10),
@@ -613,7 +568,7 @@ mod test {
             Point::new(8, 12)
         ]);
 
-        let r360 = rotate_clockwise(&r270).unwrap();
+        let r360 = rotate_clockwise(&r270);
         assert_eq!(r360.orientation, Orientation::One);
         assert_eq!(r360.points, [
             Point::new(8, 10),
@@ -627,7 +582,7 @@ mod test {
     fn test_rotate_counterclockwise_blue_ricky() {
         let origin = Point::new(6, 10);
         let piece = Piece::blue_ricky(origin);
-        let r90 = rotate_counterclockwise(&piece).unwrap();
+        let r90 = rotate_counterclockwise(&piece);
         assert_eq!(r90.orientation, Orientation::Four);
         assert_eq!(r90.points, [
             Point::new(7, 10),
@@ -636,7 +591,7 @@ mod test {
             Point::new(8, 12)
         ]);
 
-        let r180 = rotate_counterclockwise(&r90).unwrap();
+        let r180 = rotate_counterclockwise(&r90);
         assert_eq!(r180.orientation, Orientation::Three);
         assert_eq!(r180.points, [
             Point::new(6, 11),
@@ -645,7 +600,7 @@ mod test {
             Point::new(6, 12)
         ]);
 
-        let r270 = rotate_counterclockwise(&r180).unwrap();
+        let r270 = rotate_counterclockwise(&r180);
         assert_eq!(r270.orientation, Orientation::Two);
         assert_eq!(r270.points, [
             Point::new(6, 10),
@@ -654,7 +609,7 @@ mod test {
             Point::new(7, 12)
         ]);
 
-        let r360 = rotate_counterclockwise(&r270).unwrap();
+        let r360 = rotate_counterclockwise(&r270);
         assert_eq!(r360.orientation, Orientation::One);
         assert_eq!(r360.points, [
             Point::new(8, 10),
@@ -668,7 +623,7 @@ mod test {
     fn test_rotate_clockwise_orange_ricky() {
         let origin = Point::new(6, 10);
         let piece = Piece::orange_ricky(origin);
-        let r90 = rotate_clockwise(&piece).unwrap();
+        let r90 = rotate_clockwise(&piece);
         assert_eq!(r90.orientation, Orientation::Two);
         assert_eq!(r90.points, [
             Point::new(7, 10),
@@ -677,7 +632,7 @@ mod test {
             Point::new(7, 12),
         ]);
 
-        let r180 = rotate_clockwise(&r90).unwrap();
+        let r180 = rotate_clockwise(&r90);
         assert_eq!(r180.orientation, Orientation::Three);
         assert_eq!(r180.points, [
             Point::new(6, 11),
@@ -686,7 +641,7 @@ mod test {
             Point::new(8, 12),
         ]);
 
-        let r270 = rotate_clockwise(&r180).unwrap();
+        let r270 = rotate_clockwise(&r180);
         assert_eq!(r270.orientation, Orientation::Four);
         assert_eq!(r270.points, [
             Point::new(7, 10),
@@ -695,7 +650,7 @@ mod test {
             Point::new(7, 12),
         ]);
 
-        let r360 = rotate_clockwise(&r270).unwrap();
+        let r360 = rotate_clockwise(&r270);
         assert_eq!(r360.orientation, Orientation::One);
         assert_eq!(r360.points, [
             Point::new(6, 10),
@@ -705,11 +660,11 @@ mod test {
         ])
     }
 
-        #[test]
+    #[test]
     fn test_rotate_counterclockwise_orange_ricky() {
         let origin = Point::new(6, 10);
         let piece = Piece::orange_ricky(origin);
-        let r90 = rotate_counterclockwise(&piece).unwrap();
+        let r90 = rotate_counterclockwise(&piece);
         assert_eq!(r90.orientation, Orientation::Four);
         assert_eq!(r90.points, [
             Point::new(7, 10),
@@ -718,7 +673,7 @@ mod test {
             Point::new(7, 12),
         ]);
 
-        let r180 = rotate_counterclockwise(&r90).unwrap();
+        let r180 = rotate_counterclockwise(&r90);
         assert_eq!(r180.orientation, Orientation::Three);
         assert_eq!(r180.points, [
             Point::new(6, 11),
@@ -727,7 +682,7 @@ mod test {
             Point::new(8, 12),
         ]);
 
-        let r270 = rotate_counterclockwise(&r180).unwrap();
+        let r270 = rotate_counterclockwise(&r180);
         assert_eq!(r270.orientation, Orientation::Two);
         assert_eq!(r270.points, [
             Point::new(7, 10),
@@ -736,7 +691,7 @@ mod test {
             Point::new(7, 12),
         ]);
 
-        let r360 = rotate_counterclockwise(&r270).unwrap();
+        let r360 = rotate_counterclockwise(&r270);
         assert_eq!(r360.orientation, Orientation::One);
         assert_eq!(r360.points, [
             Point::new(6, 10),
@@ -746,54 +701,50 @@ mod test {
         ]);
     }
 
+    #[test]
+    fn test_rotate_180_hero() {
+        let origin = Point::new(6, 10);
+        let piece = Piece::hero(origin);
+        let r180 = rotate_180(&rotate_clockwise(&piece));
+        assert_eq!(r180.orientation, Orientation::Four);
+        assert_eq!(r180.points, rotate_counterclockwise(&piece).points);
+    }
 
     #[test]
-    fn test_generate_bounding_matrix_orange_ricky() {
+    fn test_rotate_180_teewee() {
         let origin = Point::new(6, 10);
-        let piece = Piece::orange_ricky(origin);
-        let matrix = generate_bounding_matrix(&piece);
-        assert_eq!(matrix.width(), 3);
-        assert_eq!(matrix.height(), 2);
-        assert_eq!(matrix.get(0, 0), Some(1));
-        assert_eq!(matrix.get(1, 0), Some(0));
-        assert_eq!(matrix.get(2, 0), Some(0));
-        assert_eq!(matrix.get(0, 1), Some(2));
-        assert_eq!(matrix.get(1, 1), Some(3));
-        assert_eq!(matrix.get(2, 1), Some(4));
+        let piece = Piece::teewee(origin);
+        let r180 = rotate_180(&piece);
+        assert_eq!(r180.orientation, Orientation::Three);
+        assert_eq!(r180.points, [
+            Point::new(6, 11),
+            Point::new(7, 11),
+            Point::new(8, 11),
+            Point::new(7, 12)
+        ]);
+
+        let r360 = rotate_180(&r180);
+        assert_eq!(r360.orientation, Orientation::One);
+        assert_eq!(r360.points, piece.points);
     }
 
     #[test]
-    fn test_transpose() {
-        let mut matrix = Matrix::new(3, 3, 0);
-
-        let mut val = 0;
-        for y in 0..=2 {
-            for x in 0..=2 {
-                val += 1;
-                matrix.set(x, y, val);
-            }
-        }
-
-        assert_eq!(matrix.get(0, 0), Some(1));
-        assert_eq!(matrix.get(1, 0), Some(2));
-        assert_eq!(matrix.get(2, 0), Some(3));
-        assert_eq!(matrix.get(0, 1), Some(4));
-        assert_eq!(matrix.get(1, 1), Some(5));
-        assert_eq!(matrix.get(2, 1), Some(6));
-        assert_eq!(matrix.get(0, 2), Some(7));
-        assert_eq!(matrix.get(1, 2), Some(8));
-        assert_eq!(matrix.get(2, 2), Some(9));
-
-        let transposed = transpose(matrix);
-
-        assert_eq!(transposed.get(0, 0), Some(9));
-        assert_eq!(transposed.get(1, 0), Some(6));
-        assert_eq!(transposed.get(2, 0), Some(3));
-        assert_eq!(transposed.get(0, 1), Some(8));
-        assert_eq!(transposed.get(1, 1), Some(5));
-        assert_eq!(transposed.get(2, 1), Some(2));
-        assert_eq!(transposed.get(0, 2), Some(7));
-        assert_eq!(transposed.get(1, 2), Some(4));
-        assert_eq!(transposed.get(2, 2), Some(1));
+    fn test_tables_match_constructors() {
+        let origin = Point::new(3, 5);
+        [
+            Piece::orange_ricky(origin),
+            Piece::blue_ricky(origin),
+            Piece::cleveland_z(origin),
+            Piece::rhode_island_z(origin),
+            Piece::hero(origin),
+            Piece::teewee(origin),
+            Piece::smashboy(origin),
+        ].iter().for_each(|piece| {
+            let expected: Vec<Point> = cells(&piece.shape, Orientation::One)
+                .iter()
+                .map(|(x, y)| Point::new(origin.x + x, origin.y + y))
+                .collect();
+            assert_eq!(piece.points, expected);
+        });
     }
 }
@@ -1,31 +1,23 @@
-pub enum RowsCleared {
-    Zero,
-    One,
-    Two,
-    Three,
-    Four,
-}
+use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Copy)]
+/// Points for clearing one to five rows at once. Bigger pieces can clear
+/// more than that, and those clears score as five.
+#[derive(Clone, Copy, Serialize, Deserialize)]
 pub struct ScoringConfig {
     pub one: usize,
     pub two: usize,
     pub three: usize,
     pub four: usize,
+    pub five: usize,
 }
 
 impl ScoringConfig {
-    pub fn new(one: usize, two: usize, three: usize, four: usize) -> Self {
-        Self { one, two, three, four }
+    pub fn new(one: usize, two: usize, three: usize, four: usize, five: usize) -> Self {
+        Self { one, two, three, four, five }
     }
 
-    pub fn score(&self, rows_cleared: &RowsCleared) -> usize {
-        match rows_cleared {
-            RowsCleared::Zero => 0,
-            RowsCleared::One => self.one,
-            RowsCleared::Two => self.two,
-            RowsCleared::Three => self.three,
-            RowsCleared::Four => self.four,
-        }
+    pub fn score(&self, rows_cleared: usize) -> usize {
+        let table = [0, self.one, self.two, self.three, self.four, self.five];
+        table[rows_cleared.min(table.len() - 1)]
     }
 }
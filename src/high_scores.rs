@@ -0,0 +1,198 @@
+use std::{
+    fmt,
+    fs,
+    path::{Path, PathBuf},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::GameMode,
+    game::{matrix, Game},
+    paths,
+};
+
+const FILE_NAME: &str = "high_scores.toml";
+const MAX_ENTRIES: usize = 10;
+pub const MAX_NAME_LENGTH: usize = 10;
+
+#[derive(Debug)]
+pub enum HighScoreError {
+    Io(PathBuf, std::io::Error),
+    Parse(PathBuf, toml::de::Error),
+    Serialize(toml::ser::Error),
+    NoDataDir,
+}
+
+impl fmt::Display for HighScoreError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HighScoreError::Io(path, err) => write!(f, "{}: {err}", path.display()),
+            HighScoreError::Parse(path, err) => write!(f, "{}: {err}", path.display()),
+            HighScoreError::Serialize(err) => write!(f, "{err}"),
+            HighScoreError::NoDataDir => write!(f, "could not locate a data directory, set $XDG_DATA_HOME or $HOME"),
+        }
+    }
+}
+
+impl std::error::Error for HighScoreError {}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HighScore {
+    pub name: String,
+    pub score: usize,
+    pub lines: usize,
+    pub mode: GameMode,
+    // the level the game was started on, counting from 1
+    pub level: usize,
+}
+
+// The best scores for each combination of game mode and starting level
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct HighScores {
+    #[serde(default)]
+    pub scores: Vec<HighScore>,
+}
+
+impl HighScores {
+    // Only the classic pieces on the standard board are ranked, so every
+    // table compares like with like. Training games don't count either.
+    pub fn is_ranked(game: &Game) -> bool {
+        !game.training
+            && game.piece_set.is_none()
+            && game.board.width() == matrix::WIDTH
+            && game.board.height() == matrix::HEIGHT
+    }
+
+    pub fn path() -> Option<PathBuf> {
+        Some(paths::data_dir()?.join(FILE_NAME))
+    }
+
+    // A missing file is just an empty table
+    pub fn load() -> Result<Self, HighScoreError> {
+        match Self::path() {
+            Some(path) => Self::load_from(&path),
+            None => Ok(Self::default()),
+        }
+    }
+
+    pub fn load_from(path: &Path) -> Result<Self, HighScoreError> {
+        match fs::read_to_string(path) {
+            Ok(src) => toml::from_str(&src)
+                .map_err(|err| HighScoreError::Parse(path.to_path_buf(), err)),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(err) => Err(HighScoreError::Io(path.to_path_buf(), err)),
+        }
+    }
+
+    pub fn save(&self) -> Result<(), HighScoreError> {
+        let path = Self::path().ok_or(HighScoreError::NoDataDir)?;
+        self.save_to(&path)
+    }
+
+    pub fn save_to(&self, path: &Path) -> Result<(), HighScoreError> {
+        let src = toml::to_string(self).map_err(HighScoreError::Serialize)?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)
+                .map_err(|err| HighScoreError::Io(dir.to_path_buf(), err))?;
+        }
+        fs::write(path, src).map_err(|err| HighScoreError::Io(path.to_path_buf(), err))
+    }
+
+    // Best first, at most MAX_ENTRIES
+    pub fn top(&self, mode: GameMode, level: usize) -> Vec<&HighScore> {
+        let mut scores: Vec<&HighScore> = self.scores
+            .iter()
+            .filter(|s| s.mode == mode && s.level == level)
+            .collect();
+        scores.sort_by_key(|s| std::cmp::Reverse(s.score));
+        scores.truncate(MAX_ENTRIES);
+        scores
+    }
+
+    pub fn qualifies(&self, mode: GameMode, level: usize, score: usize) -> bool {
+        if score == 0 { return false }
+
+        let top = self.top(mode, level);
+        top.len() < MAX_ENTRIES || top.iter().any(|s| score > s.score)
+    }
+
+    // Adds the entry, dropping whatever falls off the bottom of its table
+    pub fn insert(&mut self, entry: HighScore) {
+        let (mode, level) = (entry.mode, entry.level);
+        self.scores.push(entry);
+        let kept: Vec<HighScore> = self.top(mode, level).into_iter().cloned().collect();
+        self.scores.retain(|s| s.mode != mode || s.level != level);
+        self.scores.extend(kept);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(name: &str, score: usize) -> HighScore {
+        HighScore {
+            name: String::from(name),
+            score,
+            lines: score / 100,
+            mode: GameMode::AType,
+            level: 1,
+        }
+    }
+
+    #[test]
+    fn test_qualifies() {
+        let mut scores = HighScores::default();
+        assert!(!scores.qualifies(GameMode::AType, 1, 0));
+        assert!(scores.qualifies(GameMode::AType, 1, 10));
+
+        (1..=MAX_ENTRIES).for_each(|i| scores.insert(entry("A", i * 100)));
+        assert!(!scores.qualifies(GameMode::AType, 1, 100));
+        assert!(scores.qualifies(GameMode::AType, 1, 101));
+        assert!(scores.qualifies(GameMode::BType, 1, 100));
+        assert!(scores.qualifies(GameMode::AType, 2, 100));
+    }
+
+    #[test]
+    fn test_insert_keeps_best() {
+        let mut scores = HighScores::default();
+        (1..=MAX_ENTRIES + 2).for_each(|i| scores.insert(entry("A", i * 100)));
+        let top = scores.top(GameMode::AType, 1);
+        assert_eq!(top.len(), MAX_ENTRIES);
+        assert_eq!(top[0].score, 1200);
+        assert_eq!(top[MAX_ENTRIES - 1].score, 300);
+        assert_eq!(scores.scores.len(), MAX_ENTRIES);
+    }
+
+    #[test]
+    fn test_is_ranked() {
+        use crate::{config::Config, game::piece_set::PieceSet};
+        assert!(HighScores::is_ranked(&Game::default()));
+        let unranked = [
+            Config { training: true, ..Config::default() },
+            Config { piece_set: Some(PieceSet::built_in().remove(0)), ..Config::default() },
+            Config { board_width: Some(12), ..Config::default() },
+        ];
+        assert!(unranked.into_iter().all(|config| !HighScores::is_ranked(&Game::new(config))));
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let path = std::env::temp_dir()
+            .join(format!("ttrys-test-{}", std::process::id()))
+            .join(FILE_NAME);
+        let mut scores = HighScores::default();
+        scores.insert(entry("DAN", 4000));
+        scores.save_to(&path).unwrap();
+
+        let loaded = HighScores::load_from(&path).unwrap();
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+        assert_eq!(loaded, scores);
+    }
+
+    #[test]
+    fn test_load_missing_file() {
+        let path = std::env::temp_dir().join("ttrys-does-not-exist.toml");
+        assert_eq!(HighScores::load_from(&path).unwrap(), HighScores::default());
+    }
+}
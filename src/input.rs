@@ -0,0 +1,449 @@
+use std::{
+    collections::BTreeMap,
+    time::{Duration, Instant},
+};
+use serde::{Deserialize, Serialize};
+use termion::event::Key;
+
+use crate::game::action::Action;
+
+// A held key whose repeats come further apart than
+// this is taken to have been let go and pressed again.
+const REPEAT_GAP: Duration = Duration::from_millis(100);
+
+// Keys the game handles itself, ahead of the bindings
+pub const HELP_KEY: Key = Key::Char('?');
+pub const LEGEND_KEY: Key = Key::Char('\t');
+pub const QUIT_KEY: Key = Key::Char('q');
+const RESERVED: [Key; 4] = [Key::Ctrl('c'), QUIT_KEY, HELP_KEY, LEGEND_KEY];
+
+// Keys are named in the settings file the way they're labelled on
+// the keyboard: "a", "Space", "Left", "F1", "Ctrl-x", "Alt-x".
+pub fn parse_key(name: &str) -> Option<Key> {
+    let mut chars = name.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        return Some(Key::Char(c))
+    }
+    let key = match name {
+        "Space" => Key::Char(' '),
+        "Enter" => Key::Char('\n'),
+        "Tab" => Key::Char('\t'),
+        "Backspace" => Key::Backspace,
+        "Left" => Key::Left,
+        "Right" => Key::Right,
+        "Up" => Key::Up,
+        "Down" => Key::Down,
+        "Home" => Key::Home,
+        "End" => Key::End,
+        "PageUp" => Key::PageUp,
+        "PageDown" => Key::PageDown,
+        "Delete" => Key::Delete,
+        "Insert" => Key::Insert,
+        "Esc" => Key::Esc,
+        _ => {
+            if let Some(Ok(n @ 1..=12)) = name.strip_prefix('F').map(str::parse) {
+                return Some(Key::F(n))
+            }
+            let (prefix, rest) = name.split_once('-')?;
+            let mut chars = rest.chars();
+            match (prefix, chars.next(), chars.next()) {
+                ("Ctrl", Some(c), None) => Key::Ctrl(c),
+                ("Alt", Some(c), None) => Key::Alt(c),
+                _ => return None,
+            }
+        },
+    };
+    Some(key)
+}
+
+pub fn key_name(key: Key) -> String {
+    match key {
+        Key::Char(' ') => String::from("Space"),
+        Key::Char('\n') => String::from("Enter"),
+        Key::Char('\t') => String::from("Tab"),
+        Key::Char(c) => c.to_string(),
+        Key::Ctrl(c) => format!("Ctrl-{c}"),
+        Key::Alt(c) => format!("Alt-{c}"),
+        Key::F(n) => format!("F{n}"),
+        Key::Backspace => String::from("Backspace"),
+        Key::Left => String::from("Left"),
+        Key::Right => String::from("Right"),
+        Key::Up => String::from("Up"),
+        Key::Down => String::from("Down"),
+        Key::Home => String::from("Home"),
+        Key::End => String::from("End"),
+        Key::PageUp => String::from("PageUp"),
+        Key::PageDown => String::from("PageDown"),
+        Key::Delete => String::from("Delete"),
+        Key::Insert => String::from("Insert"),
+        Key::Esc => String::from("Esc"),
+        _ => String::from("?"),
+    }
+}
+
+// Which keys trigger each action. The settings file only needs to list
+// the actions it changes; the rest keep their default keys, minus any
+// that have been given to something else.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "BTreeMap<Action, Vec<String>>", into = "BTreeMap<Action, Vec<String>>")]
+pub struct KeyBindings {
+    bindings: BTreeMap<Action, Vec<Key>>,
+}
+
+impl KeyBindings {
+    // Gives the action just this one key, taking it from whatever had it
+    pub fn bind(&mut self, action: Action, key: Key) -> Result<(), String> {
+        check_not_reserved(key)?;
+        for keys in self.bindings.values_mut() {
+            keys.retain(|k| *k != key);
+        }
+        self.bindings.insert(action, vec![key]);
+        Ok(())
+    }
+
+    pub fn action_for(&self, key: Key) -> Option<Action> {
+        self.bindings
+            .iter()
+            .find(|(_, keys)| keys.contains(&key))
+            .map(|(action, _)| *action)
+    }
+
+    pub fn keys_for(&self, action: Action) -> &[Key] {
+        self.bindings.get(&action).map_or(&[], |keys| keys.as_slice())
+    }
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        Preset::Wasd.bindings()
+    }
+}
+
+impl TryFrom<BTreeMap<Action, Vec<String>>> for KeyBindings {
+    type Error = String;
+
+    fn try_from(raw: BTreeMap<Action, Vec<String>>) -> Result<Self, Self::Error> {
+        let mut chosen: BTreeMap<Action, Vec<Key>> = BTreeMap::new();
+        for (action, names) in &raw {
+            let keys = names
+                .iter()
+                .map(|name| parse_key(name).ok_or_else(|| format!("unknown key \"{name}\"")))
+                .collect::<Result<Vec<_>, _>>()?;
+            for key in &keys {
+                check_not_reserved(*key)?;
+                if let Some((other, _)) = chosen.iter().find(|(_, k)| k.contains(key)) {
+                    return Err(format!("\"{}\" is bound to both {} and {}", key_name(*key), other.name(), action.name()))
+                }
+            }
+            chosen.insert(*action, keys);
+        }
+
+        let mut bindings = Self::default().bindings;
+        for (action, keys) in bindings.iter_mut() {
+            match chosen.get(action) {
+                Some(chosen) => *keys = chosen.clone(),
+                None => keys.retain(|key| !chosen.values().any(|k| k.contains(key))),
+            }
+        }
+        Ok(Self { bindings })
+    }
+}
+
+impl From<KeyBindings> for BTreeMap<Action, Vec<String>> {
+    fn from(keys: KeyBindings) -> Self {
+        keys.bindings
+            .into_iter()
+            .map(|(action, keys)| (action, keys.into_iter().map(key_name).collect()))
+            .collect()
+    }
+}
+
+fn check_not_reserved(key: Key) -> Result<(), String> {
+    if RESERVED.contains(&key) {
+        return Err(format!("\"{}\" is reserved", key_name(key)))
+    }
+    Ok(())
+}
+
+// Ready-made sets of bindings to start from
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Preset {
+    Wasd,
+    Arrows,
+    Vim,
+    Guideline,
+}
+
+impl Preset {
+    pub fn all() -> [Self; 4] {
+        [Preset::Wasd, Preset::Arrows, Preset::Vim, Preset::Guideline]
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Preset::Wasd => "WASD",
+            Preset::Arrows => "Arrows",
+            Preset::Vim => "Vim",
+            Preset::Guideline => "Guideline",
+        }
+    }
+
+    pub fn bindings(&self) -> KeyBindings {
+        let keys: [Vec<Key>; 7] = match self {
+            // moves on the left hand, rotations on the right
+            Preset::Wasd => [
+                vec![Key::Char('a')],
+                vec![Key::Char('d')],
+                vec![Key::Char('s')],
+                vec![Key::Right],
+                vec![Key::Left],
+                vec![Key::Up],
+                vec![Key::Char(' ')],
+            ],
+            Preset::Arrows => [
+                vec![Key::Left],
+                vec![Key::Right],
+                vec![Key::Down],
+                vec![Key::Up],
+                vec![Key::Char('z')],
+                vec![Key::Char('a')],
+                vec![Key::Char(' ')],
+            ],
+            Preset::Vim => [
+                vec![Key::Char('h')],
+                vec![Key::Char('l')],
+                vec![Key::Char('j')],
+                vec![Key::Char('k')],
+                vec![Key::Char('u')],
+                vec![Key::Char('i')],
+                vec![Key::Char(' ')],
+            ],
+            // as laid out in the Tetris Guideline, less hard drop and hold
+            Preset::Guideline => [
+                vec![Key::Left],
+                vec![Key::Right],
+                vec![Key::Down],
+                vec![Key::Up, Key::Char('x')],
+                vec![Key::Char('z'), Key::Ctrl('z')],
+                vec![Key::Char('a')],
+                vec![Key::Esc, Key::F(1)],
+            ],
+        };
+        KeyBindings { bindings: Action::all().into_iter().zip(keys).collect() }
+    }
+
+    // The preset the bindings are an exact copy of, if any
+    pub fn matching(keys: &KeyBindings) -> Option<Self> {
+        Self::all().into_iter().find(|preset| preset.bindings() == *keys)
+    }
+}
+
+// The two players' keys in versus, one at either end of the keyboard
+pub fn versus_bindings() -> [KeyBindings; 2] {
+    let keys: [[Key; 7]; 2] = [
+        [
+            Key::Char('a'),
+            Key::Char('d'),
+            Key::Char('s'),
+            Key::Char('w'),
+            Key::Char('e'),
+            Key::Char('r'),
+            Key::Char(' '),
+        ],
+        [
+            Key::Left,
+            Key::Right,
+            Key::Down,
+            Key::Up,
+            Key::Char('.'),
+            Key::Char('/'),
+            Key::Char('p'),
+        ],
+    ];
+    keys.map(|keys| KeyBindings {
+        bindings: Action::all().into_iter().zip(keys.map(|key| vec![key])).collect(),
+    })
+}
+
+// Getting around the menus. These keys are fixed, and take in
+// the movement keys of every preset so they're always at hand.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MenuAction {
+    Up,
+    Down,
+    Left,
+    Right,
+    Select,
+    Back,
+}
+
+pub fn menu_action(key: Key) -> Option<MenuAction> {
+    let action = match key {
+        Key::Char('w') | Key::Char('k') | Key::Up => MenuAction::Up,
+        Key::Char('s') | Key::Char('j') | Key::Down => MenuAction::Down,
+        Key::Char('a') | Key::Char('h') | Key::Left => MenuAction::Left,
+        Key::Char('d') | Key::Char('l') | Key::Right => MenuAction::Right,
+        Key::Char('\n') => MenuAction::Select,
+        Key::Esc => MenuAction::Back,
+        _ => return None,
+    };
+    Some(action)
+}
+
+// Terminals report a held key as the same key pressed over and over,
+// at whatever rate the system repeats keys. DAS (the delay before a
+// held move starts repeating) and ARR (the time between repeats) can
+// only slow that down: repeats within `das` of the press are dropped,
+// and after that at most one gets through every `arr`.
+pub struct AutoRepeat {
+    das: Duration,
+    arr: Duration,
+    held: Option<Held>,
+}
+
+struct Held {
+    action: Action,
+    pressed: Instant,
+    last_seen: Instant,
+    last_moved: Instant,
+}
+
+impl AutoRepeat {
+    pub fn new(das: Duration, arr: Duration) -> Self {
+        Self { das, arr, held: None }
+    }
+
+    // Whether the action should go through to the game
+    pub fn accept(&mut self, action: Action, now: Instant) -> bool {
+        if !matches!(action, Action::MoveLeft | Action::MoveRight | Action::SoftDrop) {
+            self.held = None;
+            return true
+        }
+
+        match &mut self.held {
+            Some(held) if held.action == action && now - held.last_seen < REPEAT_GAP => {
+                held.last_seen = now;
+                if now - held.pressed < self.das || now - held.last_moved < self.arr {
+                    return false
+                }
+                held.last_moved = now;
+                true
+            },
+            _ => {
+                self.held = Some(Held { action, pressed: now, last_seen: now, last_moved: now });
+                true
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_key_names_round_trip() {
+        let keys = [
+            Key::Char('a'),
+            Key::Char(' '),
+            Key::Char('\n'),
+            Key::Left,
+            Key::PageDown,
+            Key::F(5),
+            Key::Ctrl('x'),
+            Key::Alt('h'),
+            Key::Esc,
+        ];
+        for key in keys {
+            assert_eq!(parse_key(&key_name(key)), Some(key));
+        }
+        assert_eq!(parse_key("F13"), None);
+        assert_eq!(parse_key("Shift-a"), None);
+    }
+
+    #[test]
+    fn test_rebinding_takes_key_from_default() {
+        let raw = BTreeMap::from([(Action::MoveLeft, vec![String::from("Left")])]);
+        let keys = KeyBindings::try_from(raw).unwrap();
+        assert_eq!(keys.action_for(Key::Left), Some(Action::MoveLeft));
+        assert_eq!(keys.action_for(Key::Char('a')), None);
+        assert!(keys.keys_for(Action::RotateCounterclockwise).is_empty());
+        assert_eq!(keys.action_for(Key::Right), Some(Action::RotateClockwise));
+    }
+
+    #[test]
+    fn test_rejects_conflicting_bindings() {
+        let raw = BTreeMap::from([
+            (Action::MoveLeft, vec![String::from("j")]),
+            (Action::MoveRight, vec![String::from("j")]),
+        ]);
+        let err = KeyBindings::try_from(raw).unwrap_err();
+        assert_eq!(err, "\"j\" is bound to both move_left and move_right");
+
+        let raw = BTreeMap::from([(Action::Pause, vec![String::from("q")])]);
+        assert!(KeyBindings::try_from(raw).is_err());
+    }
+
+    #[test]
+    fn test_presets() {
+        for preset in Preset::all() {
+            let keys = preset.bindings();
+            // every action has a key, and the settings file can hold them
+            assert!(Action::all().iter().all(|a| !keys.keys_for(*a).is_empty()), "{}", preset.name());
+            let raw = BTreeMap::from(keys.clone());
+            assert_eq!(KeyBindings::try_from(raw), Ok(keys.clone()));
+            assert_eq!(Preset::matching(&keys), Some(preset));
+        }
+        assert_eq!(Preset::matching(&KeyBindings::default()), Some(Preset::Wasd));
+    }
+
+    #[test]
+    fn test_versus_bindings() {
+        let [one, two] = versus_bindings();
+        for action in Action::all() {
+            let keys = [one.keys_for(action), two.keys_for(action)];
+            assert!(keys.iter().all(|keys| keys.len() == 1), "{}", action.name());
+            assert!(two.action_for(keys[0][0]).is_none() && one.action_for(keys[1][0]).is_none());
+            assert!(keys.iter().all(|keys| check_not_reserved(keys[0]).is_ok()));
+        }
+    }
+
+    #[test]
+    fn test_bind() {
+        let mut keys = KeyBindings::default();
+        keys.bind(Action::RotateClockwise, Key::Char('a')).unwrap();
+        assert_eq!(keys.keys_for(Action::RotateClockwise), [Key::Char('a')]);
+        assert!(keys.keys_for(Action::MoveLeft).is_empty());
+        assert_eq!(keys.action_for(Key::Right), None);
+        assert_eq!(Preset::matching(&keys), None);
+        assert!(keys.bind(Action::Pause, Key::Char('q')).is_err());
+        assert!(keys.bind(Action::Pause, Key::Ctrl('c')).is_err());
+        assert!(keys.bind(Action::Pause, HELP_KEY).is_err());
+    }
+
+    #[test]
+    fn test_auto_repeat() {
+        let ms = Duration::from_millis;
+        let mut repeat = AutoRepeat::new(ms(150), ms(50));
+        let start = Instant::now();
+        assert!(repeat.accept(Action::MoveLeft, start));
+        // the terminal repeating the held key
+        assert!(!repeat.accept(Action::MoveLeft, start + ms(30)));
+        assert!(!repeat.accept(Action::MoveLeft, start + ms(120)));
+        assert!(repeat.accept(Action::MoveLeft, start + ms(150)));
+        assert!(!repeat.accept(Action::MoveLeft, start + ms(180)));
+        assert!(repeat.accept(Action::MoveLeft, start + ms(210)));
+        // let go and pressed again
+        assert!(repeat.accept(Action::MoveLeft, start + ms(500)));
+        // rotations never repeat-limit
+        assert!(repeat.accept(Action::RotateClockwise, start + ms(510)));
+        assert!(repeat.accept(Action::RotateClockwise, start + ms(520)));
+    }
+
+    #[test]
+    fn test_auto_repeat_disabled() {
+        let mut repeat = AutoRepeat::new(Duration::ZERO, Duration::ZERO);
+        let start = Instant::now();
+        assert!((0..10).all(|i| repeat.accept(Action::SoftDrop, start + Duration::from_millis(i * 20))));
+    }
+}
@@ -0,0 +1,42 @@
+//! The ttrys engine and terminal front end, as a library.
+//!
+//! [`Game`] holds a whole game: the board (a [`Matrix`] of cells), the
+//! falling [`Piece`], the [`Level`] with its [`ScoringConfig`], and the
+//! stats. It's driven by [`Game::on_action`] for the player's input and
+//! [`Game::on_tick`] a hundred times a second, or by [`Game::step`], which
+//! does both and returns the [`GameEvent`]s that came of them. It knows
+//! nothing about terminals, so it can be played from tools, bots and tests:
+//!
+//! ```
+//! use ttrs::{config::Config, game::action::Action, Game};
+//!
+//! let mut game = Game::new(Config { seed: Some(7), ..Config::default() });
+//! game.on_action(Action::MoveLeft);
+//! while !game.game_over {
+//!     game.on_tick();
+//! }
+//! println!("scored {}", game.stats.score);
+//! ```
+//!
+//! [`config`] sets up new games, [`ui`] draws them with ratatui and [`ai`]
+//! plays them. [`env`] wraps them up for reinforcement learning, and
+//! [`bench`] plays batches of them to measure the AI and the engine.
+//! [`versus`] pits two of them against each other.
+
+pub mod ai;
+pub mod bench;
+pub mod config;
+pub mod env;
+pub mod game;
+pub mod high_scores;
+pub mod input;
+pub mod paths;
+pub mod pause_menu;
+pub mod replay;
+pub mod saved_game;
+pub mod settings;
+pub mod tbp;
+pub mod ui;
+pub mod versus;
+
+pub use game::{event::GameEvent, level::Level, matrix::Matrix, piece::Piece, scoring::ScoringConfig, Game};
@@ -1,9 +1,11 @@
-#![allow(dead_code)]
 use std::{
+    fmt::Display,
+    fs,
     thread,
-    time::Duration,
+    time::{Duration, Instant},
     sync::mpsc,
-    io::{stdout, stdin}
+    io::{stdout, stdin},
+    process,
 };
 use termion::{
     raw::IntoRawMode,
@@ -16,47 +18,180 @@ use ratatui::{
     Terminal,
 };
 
-use crate::{
-    config::Config,
-    game::Game,
+use ttrs::{
+    ai::{Ai, Hint, Weights},
+    bench,
+    config::{Config, StartMode},
+    game::{action::Action, piece_set::PieceSet, Game, Phase},
+    high_scores::{HighScore, HighScores, MAX_NAME_LENGTH},
+    input::{menu_action, versus_bindings, AutoRepeat, MenuAction, HELP_KEY, LEGEND_KEY, QUIT_KEY},
+    pause_menu::PauseMenu,
+    replay::{Playback, Replay},
+    tbp::{Bot, Process, TbpError},
+    saved_game,
+    settings::Settings,
+    ui::{self, Hud},
+    versus::Versus,
 };
+use crate::cli::{Bench, Cli, USAGE};
 
-mod config;
-mod game;
-mod ui;
+mod cli;
+
+const MAX_REPLAY_SPEED: usize = 16;
+// the AI makes a move every this many ticks, so it can be followed
+const AI_INPUT_TICKS: usize = 4;
+
+// How a game gets under way
+enum Start {
+    New(Box<Config>),
+    Resume(Box<Game>),
+    // the AI plays while the player watches
+    Demo(Box<Config>),
+    // two players side by side on the one keyboard
+    Versus(Box<Config>),
+}
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // problems with the arguments and files are reported before
+    // the terminal is taken over, so the message stays visible
+    let cli = Cli::parse(std::env::args().skip(1))
+        .unwrap_or_else(|err| exit_with(format!("{err}\nRun with --help to see the options")));
+    if cli.help {
+        println!("{USAGE}");
+        return Ok(())
+    }
+    let saved_settings = Settings::load().unwrap_or_else(|err| exit_with(err));
+    let mut settings = saved_settings.clone();
+    cli.apply_to_settings(&mut settings);
+    if let Some(name) = &cli.pieces {
+        let names: Vec<String> = PieceSet::available().into_iter().map(|set| set.name).collect();
+        if !names.contains(name) {
+            exit_with(format!("no piece set named \"{name}\", the choices are: {}", names.join(", ")))
+        }
+    }
+    if let Some(bench) = &cli.bench {
+        run_bench(bench, &cli, &settings).unwrap_or_else(|err| exit_with(err));
+        return Ok(())
+    }
+    let playback = cli.replay
+        .as_ref()
+        .map(|path| Replay::load(path).and_then(Playback::new))
+        .transpose()
+        .unwrap_or_else(|err| exit_with(err));
+
+    // the bot is started up front, so one that won't
+    // run is reported like any other bad option
+    let bot = cli.bot
+        .as_deref()
+        .map(Bot::spawn)
+        .transpose()
+        .unwrap_or_else(|err| exit_with(err));
+
+    if cli.headless {
+        let mut config = Config::from_settings(&settings);
+        cli.apply_to_config(&mut config);
+        run_headless(playback, config, bot).unwrap_or_else(|err| exit_with(err));
+        return Ok(())
+    }
+
     let stdout = stdout()
         .into_raw_mode()?
         .into_alternate_screen()?;
     let backend = TermionBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
     let events = setup_events(Duration::from_millis(10));
-    let config = configure(&mut terminal, &events)?;
-    let mut game = Game::new(config);
-    run_game(&mut game, &mut terminal, &events)?;
+    if let Some(mut playback) = playback {
+        return watch_replay(&mut playback, &mut terminal, &events)
+    }
+
+    let start = if cli.skips_menu() {
+        let finesse = bot.is_none();
+        Start::New(Box::new(Config { finesse, ..Config::from_settings(&settings) }))
+    } else {
+        configure(&mut settings, &saved_settings, &mut terminal, &events)?
+    };
+    // a resumed game isn't recorded, since it can't be played back from its start
+    let (mut game, mut replay) = match start {
+        Start::New(mut config) => {
+            cli.apply_to_config(&mut config);
+            // finesse is only counted for a person playing
+            config.finesse &= bot.is_none();
+            let mut game = Game::new(*config);
+            if let Some(mut bot) = bot {
+                bot.start(&game)?;
+                return watch(&mut game, |game| Ok(bot.next_action(game)?), &mut terminal, &events)
+            }
+            let replay = Replay::new(&game);
+            (game, Some(replay))
+        },
+        Start::Resume(game) => (*game, None),
+        Start::Demo(mut config) => {
+            cli.apply_to_config(&mut config);
+            let mut ai = Ai::default();
+            return watch(&mut Game::new(*config), |game| Ok(ai.next_action(game)), &mut terminal, &events)
+        },
+        Start::Versus(mut config) => {
+            cli.apply_to_config(&mut config);
+            return run_versus(&mut Versus::new(*config), &settings, &mut terminal, &events)
+        },
+    };
+    run_game(&mut game, replay.as_mut(), &settings, &mut terminal, &events)?;
+    if let Some(replay) = replay {
+        replay.save()?;
+    }
+    // getting through the last level is the best way to finish, and
+    // is scored the same as topping out
+    if game.game_over || game.completed {
+        game_over(&mut game, &mut terminal, &events)?;
+    }
 
     Ok(())
 }
 
+// Whatever was changed in the menu is saved and carried into the game.
+// `settings` are the ones for this run and `saved` the ones on file.
 fn configure<B: Backend>(
+    settings: &mut Settings,
+    saved: &Settings,
     terminal: &mut Terminal<B>,
     events: &mpsc::Receiver<Event>
-) -> Result<Config, Box<dyn std::error::Error>> {
-    let mut config_builder = Config::builder();
+) -> Result<Start, Box<dyn std::error::Error>> {
+    let mut config_builder = Config::builder(settings.clone());
 
     loop {
         terminal.draw(|f| ui::draw_config(f, &mut config_builder))?;
 
-        if config_builder.is_configured() { return Ok(config_builder.build()) }
+        if config_builder.is_configured() {
+            let changed = config_builder.changed_settings(saved);
+            if changed != *saved {
+                changed.save()?;
+            }
+            *settings = config_builder.settings();
+            if let Some(game) = config_builder.resumed_game() {
+                saved_game::remove()?;
+                return Ok(Start::Resume(Box::new(game)))
+            }
+            let config = Box::new(config_builder.build());
+            return Ok(match config_builder.start_mode {
+                StartMode::AiPlays => Start::Demo(config),
+                StartMode::Versus => Start::Versus(config),
+                StartMode::Play | StartMode::Training => Start::New(config),
+            })
+        }
         if let Event::Input(key) = events.recv()? {
-            match key {
-                Key::Char('w') => config_builder.previous_section(),
-                Key::Char('s') => config_builder.next_section(),
-                Key::Char('a') => config_builder.on_left(),
-                Key::Char('d') => config_builder.on_right(),
-                Key::Char('\n') => config_builder.configured(),
-                _ => {}
+            // any key can be bound, including the ones that get around the menu
+            if config_builder.controls.is_waiting() {
+                config_builder.controls.press(key);
+                continue
+            }
+            match menu_action(key) {
+                Some(MenuAction::Up) => config_builder.previous_section(),
+                Some(MenuAction::Down) => config_builder.next_section(),
+                Some(MenuAction::Left) => config_builder.on_left(),
+                Some(MenuAction::Right) => config_builder.on_right(),
+                Some(MenuAction::Select) => config_builder.configured(),
+                Some(MenuAction::Back) => config_builder.back(),
+                None => {}
             }
         };
     }
@@ -64,31 +199,300 @@ fn configure<B: Backend>(
 
 fn run_game<B: Backend>(
     game: &mut Game,
+    mut replay: Option<&mut Replay>,
+    settings: &Settings,
     terminal: &mut Terminal<B>,
     events: &mpsc::Receiver<Event>
 ) -> Result<(), Box<dyn std::error::Error>> {
+    let mut auto_repeat = AutoRepeat::new(
+        Duration::from_millis(settings.das),
+        Duration::from_millis(settings.arr),
+    );
+    let mut hud = Hud { keys: Some(&settings.keys), show_legend: true, ..Hud::default() };
+    // whether opening the help paused the game, so closing it carries on
+    let mut paused_for_help = false;
+    let mut menu = PauseMenu::default();
+    let weights = Weights::default();
+    let mut hint: Option<Hint> = None;
     loop {
-        terminal.draw(|f| ui::draw(f, game))?;
+        // work out a hint once for each piece, when it comes in
+        let stale = !hint.as_ref().is_some_and(|hint| hint.is_for(game));
+        if menu.hints && stale && game.phase == Phase::Falling && !game.game_over {
+            hint = Hint::new(game, &weights);
+        }
+        let hints = if menu.hints { hint.as_ref() } else { None };
+        terminal.draw(|f| ui::draw(f, game, &Hud { pause_menu: Some(&menu), hint: hints, ..hud }))?;
 
-        if game.should_quit() { return Ok(()) }
+        if game.should_quit() || game.game_over { return Ok(()) }
         match events.recv()? {
             Event::Input(key) => match key {
                 Key::Ctrl('c') => game.quit(),
-                Key::Char('s') => game.on_down(),
-                Key::Char('a') => game.on_left(),
-                Key::Char('d') => game.on_right(),
+                QUIT_KEY => {
+                    saved_game::save(game)?;
+                    game.quit()
+                },
+                LEGEND_KEY => hud.show_legend = !hud.show_legend,
+                HELP_KEY | Key::Esc if hud.show_help || key == HELP_KEY => {
+                    hud.show_help = !hud.show_help;
+                    // pause for the help, and carry on after it unless the game
+                    // was paused already. The pause is recorded like any other
+                    // so the replay stays in step.
+                    let toggle = if hud.show_help { !game.paused } else { paused_for_help };
+                    paused_for_help = hud.show_help && toggle;
+                    if toggle {
+                        if let Some(replay) = replay.as_deref_mut() {
+                            replay.record(Action::Pause);
+                        }
+                        game.on_action(Action::Pause);
+                    }
+                },
+                _ if hud.show_help => {},
+                // the pause key still unpauses, and the rest get around the menu
+                key if game.paused && settings.keys.action_for(key) != Some(Action::Pause) => {
+                    let action = match menu_action(key) {
+                        Some(MenuAction::Up) => { menu.previous(); None },
+                        Some(MenuAction::Down) => { menu.next(); None },
+                        Some(MenuAction::Select) => menu.select(game),
+                        Some(MenuAction::Back) => Some(Action::Pause),
+                        _ => None,
+                    };
+                    if let Some(action) = action {
+                        if let Some(replay) = replay.as_deref_mut() {
+                            replay.record(action);
+                        }
+                        game.on_action(action);
+                    }
+                },
+                key => if let Some(action) = settings.keys.action_for(key) {
+                    if !auto_repeat.accept(action, Instant::now()) { continue }
+                    if let Some(replay) = replay.as_deref_mut() {
+                        replay.record(action);
+                    }
+                    game.on_action(action);
+                },
+            },
+            Event::Tick => {
+                if let Some(replay) = replay.as_deref_mut() {
+                    replay.tick();
+                }
+                game.on_tick();
+            }
+        }
+    }
+}
+
+// Each player has keys of their own, and `q` ends the match. Versus
+// matches aren't recorded and their scores don't go in the table.
+fn run_versus<B: Backend>(
+    versus: &mut Versus,
+    settings: &Settings,
+    terminal: &mut Terminal<B>,
+    events: &mpsc::Receiver<Event>
+) -> Result<(), Box<dyn std::error::Error>> {
+    let bindings = versus_bindings();
+    let mut auto_repeats = [0, 1].map(|_| AutoRepeat::new(
+        Duration::from_millis(settings.das),
+        Duration::from_millis(settings.arr),
+    ));
+    loop {
+        terminal.draw(|f| ui::draw_versus(f, versus))?;
+
+        match events.recv()? {
+            Event::Input(key) => match key {
+                Key::Ctrl('c') | Key::Char('q') => return Ok(()),
+                // the result stays up until a key is pressed
+                _ if versus.is_over() => return Ok(()),
+                key => for (player, keys) in bindings.iter().enumerate() {
+                    if let Some(action) = keys.action_for(key) {
+                        if auto_repeats[player].accept(action, Instant::now()) {
+                            versus.on_action(player, action);
+                        }
+                    }
+                },
+            },
+            Event::Tick => versus.on_tick(),
+        }
+    }
+}
+
+// Space pauses the playback, `f` doubles its speed (wrapping back
+// around to normal) and `.` steps one tick at a time while paused.
+fn watch_replay<B: Backend>(
+    playback: &mut Playback,
+    terminal: &mut Terminal<B>,
+    events: &mpsc::Receiver<Event>
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut paused = false;
+    let mut speed = 1;
+    loop {
+        terminal.draw(|f| ui::draw(f, &mut playback.game, &Hud::default()))?;
+
+        match events.recv()? {
+            Event::Input(key) => match key {
+                Key::Ctrl('c') | Key::Char('q') => return Ok(()),
+                Key::Char(' ') => paused = !paused,
+                Key::Char('f') => speed = if speed >= MAX_REPLAY_SPEED { 1 } else { speed * 2 },
+                Key::Char('.') if paused => playback.step(),
+                _ => {}
+            },
+            Event::Tick => if !paused {
+                (0..speed).for_each(|_| playback.step());
+            }
+        }
+    }
+}
+
+// The AI or a bot plays while the player watches. Space pauses it, and
+// `q` stops it. Its games aren't recorded and its scores don't go in the table.
+fn watch<B: Backend>(
+    game: &mut Game,
+    mut next_action: impl FnMut(&Game) -> Result<Option<Action>, Box<dyn std::error::Error>>,
+    terminal: &mut Terminal<B>,
+    events: &mpsc::Receiver<Event>
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut ticks = 0;
+    loop {
+        terminal.draw(|f| ui::draw(f, game, &Hud::default()))?;
+
+        match events.recv()? {
+            Event::Input(key) => match key {
+                Key::Ctrl('c') | Key::Char('q') => return Ok(()),
                 Key::Char(' ') => game.toggle_pause(),
-                Key::Left => game.on_rotate_counterclockwise(),
-                Key::Right => game.on_rotate_clockwise(),
                 _ => {}
             },
             Event::Tick => {
+                ticks += 1;
+                if ticks % AI_INPUT_TICKS == 0 {
+                    if let Some(action) = next_action(game)? {
+                        game.on_action(action);
+                    }
+                }
                 game.on_tick();
             }
         }
     }
 }
 
+// Record the score if it makes the table for this mode and starting level,
+// otherwise leave the final board up until the player quits.
+fn game_over<B: Backend>(
+    game: &mut Game,
+    terminal: &mut Terminal<B>,
+    events: &mpsc::Receiver<Event>
+) -> Result<(), Box<dyn std::error::Error>> {
+    let (mode, level) = (game.game_mode, game.initial_level + 1);
+    let mut high_scores = HighScores::load()?;
+    let score = game.stats.score;
+    if !HighScores::is_ranked(game) || !high_scores.qualifies(mode, level, score) {
+        loop {
+            terminal.draw(|f| ui::draw(f, game, &Hud::default()))?;
+            if let Event::Input(Key::Ctrl('c')) = events.recv()? { return Ok(()) }
+        }
+    }
+
+    let Some(name) = enter_name(game, terminal, events)? else { return Ok(()) };
+    high_scores.insert(HighScore {
+        name,
+        score,
+        lines: game.stats.rows_cleared,
+        mode,
+        level,
+    });
+    high_scores.save()?;
+
+    loop {
+        terminal.draw(|f| ui::draw_high_scores(f, &high_scores, mode, level))?;
+        if let Event::Input(_) = events.recv()? { return Ok(()) }
+    }
+}
+
+// None if the player backs out with Ctrl-C
+fn enter_name<B: Backend>(
+    game: &mut Game,
+    terminal: &mut Terminal<B>,
+    events: &mpsc::Receiver<Event>
+) -> Result<Option<String>, Box<dyn std::error::Error>> {
+    let mut name = String::new();
+    loop {
+        terminal.draw(|f| {
+            ui::draw(f, game, &Hud::default());
+            ui::draw_name_entry(f, &name, game.stats.score);
+        })?;
+
+        if let Event::Input(key) = events.recv()? {
+            match key {
+                Key::Ctrl('c') => return Ok(None),
+                Key::Char('\n') if !name.trim().is_empty() => return Ok(Some(name.trim().to_string())),
+                Key::Char(c) if (c.is_alphanumeric() || c == ' ') && name.len() < MAX_NAME_LENGTH => name.push(c),
+                Key::Backspace => { name.pop(); },
+                _ => {}
+            }
+        };
+    }
+}
+
+// Plays a replay, or a game nobody (or only the bot) touches,
+// through to the end without a terminal and prints how it went.
+fn run_headless(playback: Option<Playback>, config: Config, mut bot: Option<Bot<Process>>) -> Result<(), TbpError> {
+    let game = match playback {
+        Some(mut playback) => {
+            while !playback.is_finished() {
+                playback.step();
+            }
+            playback.game
+        },
+        None => {
+            let mut game = Game::new(config);
+            if let Some(bot) = &mut bot {
+                bot.start(&game)?;
+            }
+            while !game.game_over && !game.should_quit() {
+                if let Some(bot) = &mut bot {
+                    if let Some(action) = bot.next_action(&game)? {
+                        game.on_action(action);
+                    }
+                }
+                game.on_tick();
+            }
+            game
+        },
+    };
+    println!("score {}", game.stats.score);
+    println!("lines {}", game.stats.rows_cleared);
+    println!("level {}", game.level.number);
+    Ok(())
+}
+
+// Plays the games and prints how they went, on seeds from --seed on
+fn run_bench(bench: &Bench, cli: &Cli, settings: &Settings) -> Result<(), Box<dyn std::error::Error>> {
+    let weights = match &bench.weights {
+        Some(path) => {
+            let src = fs::read_to_string(path).map_err(|err| format!("{}: {err}", path.display()))?;
+            toml::from_str(&src).map_err(|err| format!("{}: {err}", path.display()))?
+        },
+        None => Weights::default(),
+    };
+    let mut config = Config::from_settings(settings);
+    cli.apply_to_config(&mut config);
+    let first = cli.seed.unwrap_or(0);
+    let threads = bench.threads.unwrap_or_else(|| thread::available_parallelism().map_or(1, |n| n.get()));
+    let last = first
+        .checked_add(bench.games)
+        .ok_or_else(|| format!("--seed {first} leaves no room for {} games", bench.games))?;
+    let report = bench::run(&config, &weights, first..last, threads);
+    if bench.json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        println!("{report}");
+    }
+    Ok(())
+}
+
+fn exit_with(err: impl Display) -> ! {
+    eprintln!("ttrys: {err}");
+    process::exit(2)
+}
+
 enum Event {
     Input(Key),
     Tick,
@@ -0,0 +1,19 @@
+use std::path::{Path, PathBuf};
+
+// $XDG_CONFIG_HOME/ttrys, falling back to ~/.config/ttrys
+pub fn config_dir() -> Option<PathBuf> {
+    xdg_dir("XDG_CONFIG_HOME", ".config")
+}
+
+// $XDG_DATA_HOME/ttrys, falling back to ~/.local/share/ttrys
+pub fn data_dir() -> Option<PathBuf> {
+    xdg_dir("XDG_DATA_HOME", ".local/share")
+}
+
+fn xdg_dir(var: &str, fallback: &str) -> Option<PathBuf> {
+    let base = std::env::var_os(var)
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(fallback)))?;
+    Some(base.join("ttrys"))
+}
@@ -0,0 +1,88 @@
+use crate::game::{action::Action, Game};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PauseItem {
+    Resume,
+    Ghost,
+    Hints,
+}
+
+impl PauseItem {
+    pub fn all() -> [Self; 3] {
+        [PauseItem::Resume, PauseItem::Ghost, PauseItem::Hints]
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            PauseItem::Resume => "Resume",
+            PauseItem::Ghost => "Ghost",
+            PauseItem::Hints => "Hints",
+        }
+    }
+}
+
+// The menu over a paused game, for switching the aids on and off
+#[derive(Debug, Default)]
+pub struct PauseMenu {
+    pub selected: usize,
+    // show where the AI would put each piece, and the keys to get it there
+    pub hints: bool,
+}
+
+impl PauseMenu {
+    pub fn selected_item(&self) -> PauseItem {
+        PauseItem::all()[self.selected]
+    }
+
+    pub fn previous(&mut self) {
+        self.selected = self.selected.checked_sub(1).unwrap_or(PauseItem::all().len() - 1);
+    }
+
+    pub fn next(&mut self) {
+        self.selected = (self.selected + 1) % PauseItem::all().len();
+    }
+
+    // Switches the selected aid. Resume is handed back as the pause
+    // action, for the caller to unpause the game and record it.
+    pub fn select(&mut self, game: &mut Game) -> Option<Action> {
+        match self.selected_item() {
+            PauseItem::Resume => return Some(Action::Pause),
+            PauseItem::Ghost => game.show_ghost = !game.show_ghost,
+            PauseItem::Hints => self.hints = !self.hints,
+        }
+        None
+    }
+
+    // Whether the item is switched on, for the ones that switch
+    pub fn is_on(&self, item: PauseItem, game: &Game) -> Option<bool> {
+        match item {
+            PauseItem::Resume => None,
+            PauseItem::Ghost => Some(game.show_ghost),
+            PauseItem::Hints => Some(self.hints),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pause_menu() {
+        let mut game = Game::default();
+        let mut menu = PauseMenu::default();
+        menu.previous();
+        assert_eq!(menu.selected_item(), PauseItem::Hints);
+        assert_eq!(menu.select(&mut game), None);
+        assert_eq!(menu.is_on(PauseItem::Hints, &game), Some(true));
+
+        menu.previous();
+        let ghost = game.show_ghost;
+        menu.select(&mut game);
+        assert_eq!(game.show_ghost, !ghost);
+
+        menu.next();
+        menu.next();
+        assert_eq!(menu.select(&mut game), Some(Action::Pause));
+    }
+}
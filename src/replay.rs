@@ -0,0 +1,309 @@
+use std::{
+    fmt,
+    fs,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::{Config, GameMode},
+    game::{action::Action, piece_set::PieceSet, Game},
+    paths,
+};
+
+const DIR_NAME: &str = "replays";
+
+#[derive(Debug)]
+pub enum ReplayError {
+    Io(PathBuf, std::io::Error),
+    Format(PathBuf, serde_json::Error),
+    UnknownPieceSet(String),
+    NoDataDir,
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReplayError::Io(path, err) => write!(f, "{}: {err}", path.display()),
+            ReplayError::Format(path, err) => write!(f, "{}: {err}", path.display()),
+            ReplayError::UnknownPieceSet(name) => write!(f, "the replay uses a piece set named \"{name}\", which isn't installed"),
+            ReplayError::NoDataDir => write!(f, "could not locate a data directory, set $XDG_DATA_HOME or $HOME"),
+        }
+    }
+}
+
+impl std::error::Error for ReplayError {}
+
+// Everything needed to play a game back: how it was set up,
+// and every input along with the tick it arrived on.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct Replay {
+    pub seed: u64,
+    pub game_mode: GameMode,
+    pub initial_level: usize,
+    // looked up by name among the available sets on playback
+    pub piece_set: Option<String>,
+    #[serde(default)]
+    pub board_width: Option<usize>,
+    #[serde(default)]
+    pub board_height: Option<usize>,
+    #[serde(default)]
+    pub line_clear_frames: usize,
+    #[serde(default)]
+    pub entry_delay: usize,
+    #[serde(default)]
+    pub training: bool,
+    // how many ticks the game ran for
+    pub ticks: usize,
+    #[serde(with = "compact_inputs")]
+    pub inputs: Vec<(usize, Action)>,
+}
+
+impl Replay {
+    // Starts recording a game that hasn't been played yet
+    pub fn new(game: &Game) -> Self {
+        Self {
+            seed: game.seed,
+            game_mode: game.game_mode,
+            initial_level: game.initial_level,
+            piece_set: game.piece_set.as_ref().map(|set| set.name.clone()),
+            board_width: Some(game.board.width()),
+            board_height: Some(game.board.height()),
+            line_clear_frames: game.line_clear_frames,
+            entry_delay: game.entry_delay,
+            training: game.training,
+            ticks: 0,
+            inputs: vec![],
+        }
+    }
+
+    pub fn record(&mut self, action: Action) {
+        self.inputs.push((self.ticks, action));
+    }
+
+    pub fn tick(&mut self) {
+        self.ticks += 1;
+    }
+
+    pub fn dir() -> Option<PathBuf> {
+        Some(paths::data_dir()?.join(DIR_NAME))
+    }
+
+    // Named for the time it's saved at; returns where it was written
+    pub fn save(&self) -> Result<PathBuf, ReplayError> {
+        let secs = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default();
+        let path = Self::dir()
+            .ok_or(ReplayError::NoDataDir)?
+            .join(format!("{secs}.json"));
+        self.save_to(&path)?;
+        Ok(path)
+    }
+
+    pub fn save_to(&self, path: &Path) -> Result<(), ReplayError> {
+        let src = serde_json::to_string(self)
+            .map_err(|err| ReplayError::Format(path.to_path_buf(), err))?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)
+                .map_err(|err| ReplayError::Io(dir.to_path_buf(), err))?;
+        }
+        fs::write(path, src).map_err(|err| ReplayError::Io(path.to_path_buf(), err))
+    }
+
+    pub fn load(path: &Path) -> Result<Self, ReplayError> {
+        let src = fs::read_to_string(path)
+            .map_err(|err| ReplayError::Io(path.to_path_buf(), err))?;
+        serde_json::from_str(&src).map_err(|err| ReplayError::Format(path.to_path_buf(), err))
+    }
+
+    fn config(&self) -> Result<Config, ReplayError> {
+        let piece_set = match &self.piece_set {
+            Some(name) => Some(
+                PieceSet::available()
+                    .into_iter()
+                    .find(|set| &set.name == name)
+                    .ok_or_else(|| ReplayError::UnknownPieceSet(name.clone()))?
+            ),
+            None => None,
+        };
+        Ok(Config {
+            game_mode: self.game_mode,
+            initial_level: self.initial_level,
+            piece_set,
+            seed: Some(self.seed),
+            board_width: self.board_width,
+            board_height: self.board_height,
+            line_clear_frames: self.line_clear_frames,
+            entry_delay: self.entry_delay,
+            training: self.training,
+            ..Config::default()
+        })
+    }
+}
+
+// Feeds a replay's inputs back into a fresh game, tick by tick
+pub struct Playback {
+    pub game: Game,
+    replay: Replay,
+    tick: usize,
+    next_input: usize,
+}
+
+impl Playback {
+    pub fn new(replay: Replay) -> Result<Self, ReplayError> {
+        let game = Game::new(replay.config()?);
+        Ok(Self { game, replay, tick: 0, next_input: 0 })
+    }
+
+    // Inputs recorded after the last tick (like the drop that
+    // ended the game) get one more step of their own.
+    pub fn step(&mut self) {
+        if self.is_finished() { return }
+
+        while let Some((tick, action)) = self.replay.inputs.get(self.next_input) {
+            if *tick != self.tick { break }
+            self.game.on_action(*action);
+            self.next_input += 1;
+        }
+        if self.tick < self.replay.ticks {
+            self.game.on_tick();
+        }
+        self.tick += 1;
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.tick > self.replay.ticks
+    }
+}
+
+// Inputs are written as one string of "<ticks since the last input><action>"
+// pairs, e.g. "12L0C40D", which keeps a long game down to a few kilobytes.
+mod compact_inputs {
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+    use super::Action;
+
+    const CODES: [(Action, char); 7] = [
+        (Action::MoveLeft, 'L'),
+        (Action::MoveRight, 'R'),
+        (Action::SoftDrop, 'D'),
+        (Action::RotateClockwise, 'C'),
+        (Action::RotateCounterclockwise, 'A'),
+        (Action::Rotate180, 'H'),
+        (Action::Pause, 'P'),
+    ];
+
+    pub fn serialize<S: Serializer>(inputs: &[(usize, Action)], serializer: S) -> Result<S::Ok, S::Error> {
+        let mut out = String::new();
+        let mut last = 0;
+        for (tick, action) in inputs {
+            let (_, code) = CODES.iter().find(|(a, _)| a == action).unwrap();
+            out.push_str(&format!("{}{code}", tick - last));
+            last = *tick;
+        }
+        serializer.serialize_str(&out)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<(usize, Action)>, D::Error> {
+        let src = String::deserialize(deserializer)?;
+        let mut inputs = vec![];
+        let mut tick = 0;
+        let mut digits = String::new();
+        for c in src.chars() {
+            if c.is_ascii_digit() {
+                digits.push(c);
+                continue
+            }
+            let (action, _) = CODES
+                .iter()
+                .find(|(_, code)| *code == c)
+                .ok_or_else(|| D::Error::custom(format!("unknown input code '{c}'")))?;
+            tick += digits.parse::<usize>()
+                .map_err(|_| D::Error::custom(format!("missing tick count before '{c}'")))?;
+            digits.clear();
+            inputs.push((tick, *action));
+        }
+        if !digits.is_empty() {
+            return Err(D::Error::custom("inputs end with a tick count and no action"))
+        }
+        Ok(inputs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{seq::SliceRandom, Rng, SeedableRng};
+    use rand_pcg::Pcg64;
+
+    #[test]
+    fn test_compact_inputs() {
+        let json = r#"{"seed":7,"game_mode":"a-type","initial_level":0,"piece_set":null,"board_width":10,"board_height":20,"line_clear_frames":0,"entry_delay":0,"training":false,"ticks":60,"inputs":"12L0C40D"}"#;
+        let replay: Replay = serde_json::from_str(json).unwrap();
+        assert_eq!(replay.inputs, [
+            (12, Action::MoveLeft),
+            (12, Action::RotateClockwise),
+            (52, Action::SoftDrop),
+        ]);
+        assert_eq!(serde_json::to_string(&replay).unwrap(), json);
+    }
+
+    #[test]
+    fn test_rejects_unknown_input() {
+        let json = r#"{"seed":7,"game_mode":"a-type","initial_level":0,"piece_set":null,"ticks":60,"inputs":"12X"}"#;
+        assert!(serde_json::from_str::<Replay>(json).is_err());
+    }
+
+    fn record_random_game(config: Config) -> (Game, Replay) {
+        let mut rng = Pcg64::seed_from_u64(1);
+        let mut game = Game::new(config);
+        let mut replay = Replay::new(&game);
+        let actions: Vec<Action> = Action::all()
+            .into_iter()
+            .filter(|a| *a != Action::Pause)
+            .collect();
+        while !game.game_over && replay.ticks < 20_000 {
+            if rng.gen_bool(0.2) {
+                let action = *actions.choose(&mut rng).unwrap();
+                replay.record(action);
+                game.on_action(action);
+            }
+            replay.tick();
+            game.on_tick();
+        }
+        (game, replay)
+    }
+
+    fn play_back(replay: Replay) -> Game {
+        let mut playback = Playback::new(replay).unwrap();
+        while !playback.is_finished() {
+            playback.step();
+        }
+        playback.game
+    }
+
+    #[test]
+    fn test_playback_matches_recording() {
+        let config = Config { game_mode: GameMode::BType, ..Config::default() };
+        let (game, replay) = record_random_game(config);
+        let src = serde_json::to_string(&replay).unwrap();
+        let played = play_back(serde_json::from_str(&src).unwrap());
+
+        assert_eq!(played.board, game.board);
+        assert_eq!(played.stats.score, game.stats.score);
+        assert_eq!(played.stats.rows_cleared, game.stats.rows_cleared);
+        assert_eq!(played.game_over, game.game_over);
+    }
+
+    #[test]
+    fn test_playback_with_piece_set() {
+        let pentominoes = PieceSet::built_in().remove(0);
+        let config = Config { piece_set: Some(pentominoes), ..Config::default() };
+        let (game, replay) = record_random_game(config);
+        assert_eq!(replay.piece_set.as_deref(), Some("Pentominoes"));
+        let played = play_back(replay);
+        assert_eq!(played.board, game.board);
+    }
+}
@@ -0,0 +1,134 @@
+use std::{
+    fmt,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{game::Game, paths};
+
+const FILE_NAME: &str = "saved_game.json";
+
+#[derive(Debug)]
+pub enum SaveError {
+    Io(PathBuf, std::io::Error),
+    Format(PathBuf, serde_json::Error),
+    NoDataDir,
+}
+
+impl fmt::Display for SaveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SaveError::Io(path, err) => write!(f, "{}: {err}", path.display()),
+            SaveError::Format(path, err) => write!(f, "{}: {err}", path.display()),
+            SaveError::NoDataDir => write!(f, "could not locate a data directory, set $XDG_DATA_HOME or $HOME"),
+        }
+    }
+}
+
+impl std::error::Error for SaveError {}
+
+pub fn path() -> Option<PathBuf> {
+    Some(paths::data_dir()?.join(FILE_NAME))
+}
+
+pub fn save(game: &Game) -> Result<(), SaveError> {
+    let path = path().ok_or(SaveError::NoDataDir)?;
+    save_to(game, &path)
+}
+
+pub fn save_to(game: &Game, path: &Path) -> Result<(), SaveError> {
+    let src = serde_json::to_string(game)
+        .map_err(|err| SaveError::Format(path.to_path_buf(), err))?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)
+            .map_err(|err| SaveError::Io(dir.to_path_buf(), err))?;
+    }
+    fs::write(path, src).map_err(|err| SaveError::Io(path.to_path_buf(), err))
+}
+
+// None when there's no game waiting to be resumed
+pub fn load() -> Result<Option<Game>, SaveError> {
+    match path() {
+        Some(path) => load_from(&path),
+        None => Ok(None),
+    }
+}
+
+// A resumed game starts out paused so the player can find their bearings.
+pub fn load_from(path: &Path) -> Result<Option<Game>, SaveError> {
+    match fs::read_to_string(path) {
+        Ok(src) => {
+            let mut game: Game = serde_json::from_str(&src)
+                .map_err(|err| SaveError::Format(path.to_path_buf(), err))?;
+            game.paused = true;
+            Ok(Some(game))
+        },
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(SaveError::Io(path.to_path_buf(), err)),
+    }
+}
+
+// Called once a saved game has been picked back up, so it can't be resumed twice.
+pub fn remove() -> Result<(), SaveError> {
+    let Some(path) = path() else { return Ok(()) };
+    match fs::remove_file(&path) {
+        Err(err) if err.kind() != std::io::ErrorKind::NotFound => Err(SaveError::Io(path, err)),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{config::Config, game::piece_set::PieceSet};
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir()
+            .join(format!("ttrys-test-{}-{name}", std::process::id()))
+            .join(FILE_NAME)
+    }
+
+    fn round_trip(mut game: Game, name: &str) {
+        (0..30).for_each(|_| game.on_down());
+        let path = temp_path(name);
+        save_to(&game, &path).unwrap();
+        let mut resumed = load_from(&path).unwrap().unwrap();
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+
+        assert!(resumed.paused);
+        resumed.toggle_pause();
+        assert_eq!(resumed.board, game.board);
+        assert_eq!(resumed.current_piece, game.current_piece);
+        assert_eq!(resumed.next_piece, game.next_piece);
+        assert_eq!(resumed.stats.score, game.stats.score);
+        assert_eq!(resumed.level.number, game.level.number);
+        assert_eq!(resumed.level.counter, game.level.counter);
+        assert_eq!(resumed.levels.len(), game.levels.len());
+
+        // the random state carries over, so both deal the same pieces from here on
+        (0..100).for_each(|_| {
+            game.on_down();
+            resumed.on_down();
+        });
+        assert_eq!(resumed.board, game.board);
+        assert_eq!(resumed.next_piece, game.next_piece);
+    }
+
+    #[test]
+    fn test_save_and_resume_classic() {
+        round_trip(Game::default(), "classic");
+    }
+
+    #[test]
+    fn test_save_and_resume_piece_set() {
+        let pentominoes = PieceSet::built_in().remove(0);
+        let config = Config { piece_set: Some(pentominoes), ..Config::default() };
+        round_trip(Game::new(config), "pentominoes");
+    }
+
+    #[test]
+    fn test_load_missing_file() {
+        let path = std::env::temp_dir().join("ttrys-does-not-exist.json");
+        assert!(load_from(&path).unwrap().is_none());
+    }
+}
@@ -0,0 +1,224 @@
+use std::{
+    fmt,
+    fs,
+    path::{Path, PathBuf},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::GameMode,
+    game::level::{Level, Theme},
+    input::KeyBindings,
+    paths,
+};
+
+const FILE_NAME: &str = "settings.toml";
+pub const MAX_PREVIEW: usize = 5;
+pub const MAX_DELAY: usize = 100;
+
+#[derive(Debug)]
+pub enum SettingsError {
+    Io(PathBuf, std::io::Error),
+    Parse(PathBuf, toml::de::Error),
+    Invalid(PathBuf, String),
+    Serialize(toml::ser::Error),
+    NoConfigDir,
+}
+
+impl fmt::Display for SettingsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SettingsError::Io(path, err) => write!(f, "{}: {err}", path.display()),
+            SettingsError::Parse(path, err) => write!(f, "{}: {err}", path.display()),
+            SettingsError::Invalid(path, msg) => write!(f, "{}: {msg}", path.display()),
+            SettingsError::Serialize(err) => write!(f, "{err}"),
+            SettingsError::NoConfigDir => write!(f, "could not locate a config directory, set $XDG_CONFIG_HOME or $HOME"),
+        }
+    }
+}
+
+impl std::error::Error for SettingsError {}
+
+// The player's choices, remembered between launches. Anything
+// missing from the file keeps its default.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Settings {
+    pub game_mode: GameMode,
+    // the starting level, counting from 1
+    pub level: usize,
+    // by name, None for the classic tetrominoes
+    pub piece_set: Option<String>,
+    // one color theme (1-10) for the whole game, rather than changing with the level
+    pub theme: Option<usize>,
+    // how many of the upcoming pieces to show
+    pub preview: usize,
+    pub ghost: bool,
+    // milliseconds, see input::AutoRepeat
+    pub das: u64,
+    pub arr: u64,
+    // in ticks, a hundred to the second
+    pub line_clear_frames: usize,
+    pub entry_delay: usize,
+    // no line clear animation or entry delay
+    pub fast: bool,
+    pub keys: KeyBindings,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            game_mode: GameMode::AType,
+            level: 1,
+            piece_set: None,
+            theme: None,
+            preview: 1,
+            ghost: false,
+            das: 0,
+            arr: 0,
+            line_clear_frames: 30,
+            entry_delay: 10,
+            fast: false,
+            keys: KeyBindings::default(),
+        }
+    }
+}
+
+impl Settings {
+    pub fn line_clear_frames(&self) -> usize {
+        if self.fast { 0 } else { self.line_clear_frames }
+    }
+
+    pub fn entry_delay(&self) -> usize {
+        if self.fast { 0 } else { self.entry_delay }
+    }
+
+    pub fn path() -> Option<PathBuf> {
+        Some(paths::config_dir()?.join(FILE_NAME))
+    }
+
+    // A missing file means the defaults
+    pub fn load() -> Result<Self, SettingsError> {
+        match Self::path() {
+            Some(path) => Self::load_from(&path),
+            None => Ok(Self::default()),
+        }
+    }
+
+    pub fn load_from(path: &Path) -> Result<Self, SettingsError> {
+        let src = match fs::read_to_string(path) {
+            Ok(src) => src,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(err) => return Err(SettingsError::Io(path.to_path_buf(), err)),
+        };
+        let settings: Self = toml::from_str(&src)
+            .map_err(|err| SettingsError::Parse(path.to_path_buf(), err))?;
+        settings.validate()
+            .map_err(|msg| SettingsError::Invalid(path.to_path_buf(), msg))?;
+        Ok(settings)
+    }
+
+    pub fn save(&self) -> Result<(), SettingsError> {
+        let path = Self::path().ok_or(SettingsError::NoConfigDir)?;
+        self.save_to(&path)
+    }
+
+    pub fn save_to(&self, path: &Path) -> Result<(), SettingsError> {
+        let src = toml::to_string(self).map_err(SettingsError::Serialize)?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)
+                .map_err(|err| SettingsError::Io(dir.to_path_buf(), err))?;
+        }
+        fs::write(path, src).map_err(|err| SettingsError::Io(path.to_path_buf(), err))
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        let levels = Level::level_count();
+        if !(1..=levels).contains(&self.level) {
+            return Err(format!("level must be between 1 and {levels}, found {}", self.level))
+        }
+        let themes = Theme::count();
+        if let Some(theme) = self.theme.filter(|t| !(1..=themes).contains(t)) {
+            return Err(format!("theme must be between 1 and {themes}, found {theme}"))
+        }
+        if self.preview > MAX_PREVIEW {
+            return Err(format!("preview can show at most {MAX_PREVIEW} pieces, found {}", self.preview))
+        }
+        for (name, frames) in [("line_clear_frames", self.line_clear_frames), ("entry_delay", self.entry_delay)] {
+            if frames > MAX_DELAY {
+                return Err(format!("{name} can be at most {MAX_DELAY}, found {frames}"))
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use termion::event::Key;
+    use crate::game::action::Action;
+
+    fn parse(name: &str, src: &str) -> Result<Settings, SettingsError> {
+        let path = std::env::temp_dir()
+            .join(format!("ttrys-test-{}-settings-{name}", std::process::id()))
+            .join(FILE_NAME);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, src).unwrap();
+        let settings = Settings::load_from(&path);
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+        settings
+    }
+
+    #[test]
+    fn test_partial_file_keeps_defaults() {
+        let settings = parse("partial", r#"
+            game_mode = "b-type"
+            ghost = true
+
+            [keys]
+            move_left = ["h", "Left"]
+        "#).unwrap();
+        assert_eq!(settings.game_mode, GameMode::BType);
+        assert!(settings.ghost);
+        assert_eq!(settings.level, 1);
+        assert_eq!(settings.preview, 1);
+        assert_eq!(settings.keys.action_for(Key::Char('h')), Some(Action::MoveLeft));
+        assert_eq!(settings.keys.action_for(Key::Left), Some(Action::MoveLeft));
+        assert_eq!(settings.keys.action_for(Key::Char('s')), Some(Action::SoftDrop));
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let path = std::env::temp_dir()
+            .join(format!("ttrys-test-{}-settings", std::process::id()))
+            .join(FILE_NAME);
+        let custom = Settings {
+            level: 4,
+            piece_set: Some(String::from("Pentominoes")),
+            theme: Some(3),
+            preview: 3,
+            das: 170,
+            arr: 50,
+            fast: true,
+            ..Settings::default()
+        };
+        for settings in [Settings::default(), custom] {
+            settings.save_to(&path).unwrap();
+            assert_eq!(Settings::load_from(&path).unwrap(), settings);
+        }
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_errors_name_the_problem() {
+        let err = parse("level", "level = 11").unwrap_err().to_string();
+        assert!(err.ends_with("level must be between 1 and 10, found 11"), "{err}");
+
+        let err = parse("keys", "[keys]\nmove_left = [\"Shift-a\"]").unwrap_err().to_string();
+        assert!(err.contains("unknown key \"Shift-a\""), "{err}");
+
+        let err = parse("unknown", "ghosts = true").unwrap_err().to_string();
+        assert!(err.contains("unknown field `ghosts`"), "{err}");
+    }
+}
@@ -0,0 +1,571 @@
+use std::{
+    fmt,
+    io::{self, BufRead, BufReader, Write},
+    process::{Child, ChildStdin, ChildStdout, Command, Stdio},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    ai::{cells, is_moving, placements, Plan},
+    game::{
+        action::Action,
+        matrix::Color,
+        piece::{Point, Shape},
+        Game,
+    },
+};
+
+// The board as bots see it, whatever part of it the game uses
+pub const BOARD_WIDTH: usize = 10;
+pub const BOARD_HEIGHT: usize = 40;
+
+#[derive(Debug)]
+pub enum TbpError {
+    Io(io::Error),
+    Format(serde_json::Error),
+    // the bot reported a problem
+    Bot(String),
+    // a message that doesn't belong at this point in the conversation
+    Unexpected(BotMessage),
+    Closed,
+    // --bot was given an empty command
+    NoCommand,
+    Unsupported(&'static str),
+    // none of the bot's suggestions could be reached
+    NoMove,
+}
+
+impl fmt::Display for TbpError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TbpError::Io(err) => write!(f, "talking to the bot: {err}"),
+            TbpError::Format(err) => write!(f, "the bot sent something other than a message: {err}"),
+            TbpError::Bot(reason) => write!(f, "the bot gave up: {reason}"),
+            TbpError::Unexpected(message) => write!(f, "the bot sent {message:?} out of turn"),
+            TbpError::Closed => write!(f, "the bot exited"),
+            TbpError::NoCommand => write!(f, "no command given for the bot"),
+            TbpError::Unsupported(what) => write!(f, "bots can't play {what}"),
+            TbpError::NoMove => write!(f, "the bot only suggested moves the piece can't reach"),
+        }
+    }
+}
+
+impl std::error::Error for TbpError {}
+
+impl From<io::Error> for TbpError {
+    fn from(err: io::Error) -> Self {
+        TbpError::Io(err)
+    }
+}
+
+// What's sent to the bot
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum FrontendMessage {
+    Rules,
+    Start(Start),
+    Stop,
+    Suggest,
+    Play {
+        #[serde(rename = "move")]
+        mv: Move,
+    },
+    NewPiece { piece: Tetromino },
+    Quit,
+}
+
+// A position for the bot to pick up from. The queue starts with the
+// piece in play, and the board's rows go up from the floor, a letter
+// in each filled cell.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Start {
+    // there's no hold in this game
+    pub hold: Option<Tetromino>,
+    pub queue: Vec<Tetromino>,
+    pub combo: u32,
+    pub back_to_back: bool,
+    pub board: Vec<Vec<Option<char>>>,
+}
+
+// What the bot sends back. Messages from newer versions of
+// the protocol are read as Unknown and passed over.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum BotMessage {
+    Info {
+        name: String,
+        version: String,
+        author: String,
+        #[serde(default)]
+        features: Vec<String>,
+    },
+    Ready,
+    Error { reason: String },
+    Suggestion { moves: Vec<Move> },
+    #[serde(other)]
+    Unknown,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Tetromino { I, O, T, L, J, S, Z }
+
+impl Tetromino {
+    // Only the classic pieces have a letter
+    pub fn of(shape: &Shape) -> Option<Self> {
+        match shape {
+            Shape::Hero => Some(Tetromino::I),
+            Shape::Smashboy => Some(Tetromino::O),
+            Shape::Teewee => Some(Tetromino::T),
+            Shape::OrangeRicky => Some(Tetromino::L),
+            Shape::BlueRicky => Some(Tetromino::J),
+            Shape::RhodeIslandZ => Some(Tetromino::S),
+            Shape::ClevelandZ => Some(Tetromino::Z),
+            Shape::Defined(_) => None,
+        }
+    }
+
+    // The cells around the piece's center pointing north, y going up
+    fn offsets(&self) -> [(i32, i32); 4] {
+        match self {
+            Tetromino::I => [(-1, 0), (0, 0), (1, 0), (2, 0)],
+            Tetromino::O => [(0, 0), (1, 0), (0, 1), (1, 1)],
+            Tetromino::T => [(-1, 0), (0, 0), (1, 0), (0, 1)],
+            Tetromino::L => [(-1, 0), (0, 0), (1, 0), (1, 1)],
+            Tetromino::J => [(-1, 0), (0, 0), (1, 0), (-1, 1)],
+            Tetromino::S => [(-1, 0), (0, 0), (0, 1), (1, 1)],
+            Tetromino::Z => [(-1, 1), (0, 1), (0, 0), (1, 0)],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Orientation { North, East, South, West }
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Spin {
+    #[default]
+    None,
+    Mini,
+    Full,
+}
+
+// Where a piece ends up, by its center cell
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Location {
+    #[serde(rename = "type")]
+    pub piece: Tetromino,
+    pub orientation: Orientation,
+    pub x: i32,
+    pub y: i32,
+}
+
+impl Location {
+    // The board cells the piece covers, in the order they sort in
+    pub fn cells(&self) -> Vec<Point> {
+        let mut points: Vec<Point> = self.piece
+            .offsets()
+            .iter()
+            .map(|&(x, y)| match self.orientation {
+                Orientation::North => (x, y),
+                Orientation::East => (y, -x),
+                Orientation::South => (-x, -y),
+                Orientation::West => (-y, x),
+            })
+            .map(|(x, y)| Point::new(self.x + x, self.y + y))
+            .collect();
+        points.sort();
+        points
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Move {
+    pub location: Location,
+    #[serde(default)]
+    pub spin: Spin,
+}
+
+// How messages get to the bot and back, one at a time
+pub trait Transport {
+    fn send(&mut self, message: &FrontendMessage) -> Result<(), TbpError>;
+    fn receive(&mut self) -> Result<BotMessage, TbpError>;
+}
+
+// A bot running as a child process, a JSON message a line
+// each way over its stdin and stdout
+pub struct Process {
+    child: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+}
+
+impl Process {
+    // The command is split on whitespace into the program and its arguments
+    pub fn spawn(command: &str) -> Result<Self, TbpError> {
+        let mut words = command.split_whitespace();
+        let program = words.next().ok_or(TbpError::NoCommand)?;
+        let mut child = Command::new(program)
+            .args(words)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            // anything it logs would end up over the game
+            .stderr(Stdio::null())
+            .spawn()?;
+        let stdin = child.stdin.take().ok_or(TbpError::Closed)?;
+        let stdout = BufReader::new(child.stdout.take().ok_or(TbpError::Closed)?);
+        Ok(Self { child, stdin, stdout })
+    }
+}
+
+impl Transport for Process {
+    fn send(&mut self, message: &FrontendMessage) -> Result<(), TbpError> {
+        let line = serde_json::to_string(message).map_err(TbpError::Format)?;
+        writeln!(self.stdin, "{line}")?;
+        Ok(self.stdin.flush()?)
+    }
+
+    fn receive(&mut self) -> Result<BotMessage, TbpError> {
+        let mut line = String::new();
+        if self.stdout.read_line(&mut line)? == 0 {
+            return Err(TbpError::Closed)
+        }
+        serde_json::from_str(&line).map_err(TbpError::Format)
+    }
+}
+
+impl Drop for Process {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// A bot speaking the Tetris Bot Protocol, playing a game an input at a
+/// time like [`Ai`](crate::ai::Ai). It's asked for a move as each piece
+/// comes in, and the piece is steered to the first suggestion it can reach.
+pub struct Bot<T: Transport> {
+    transport: T,
+    pub name: String,
+    pub version: String,
+    pub author: String,
+    // how many pieces the bot has been shown, counting from the first
+    revealed: usize,
+    plan: Option<Plan>,
+    // whether the last piece ended up somewhere other than the bot thinks
+    out_of_step: bool,
+}
+
+impl Bot<Process> {
+    pub fn spawn(command: &str) -> Result<Self, TbpError> {
+        Bot::connect(Process::spawn(command)?)
+    }
+}
+
+impl<T: Transport> Bot<T> {
+    // Waits for the bot to introduce itself, then sets out the rules
+    pub fn connect(mut transport: T) -> Result<Self, TbpError> {
+        let (name, version, author) = match receive(&mut transport)? {
+            BotMessage::Info { name, version, author, .. } => (name, version, author),
+            BotMessage::Error { reason } => return Err(TbpError::Bot(reason)),
+            message => return Err(TbpError::Unexpected(message)),
+        };
+        transport.send(&FrontendMessage::Rules)?;
+        match receive(&mut transport)? {
+            BotMessage::Ready => {},
+            BotMessage::Error { reason } => return Err(TbpError::Bot(reason)),
+            message => return Err(TbpError::Unexpected(message)),
+        }
+        Ok(Self { transport, name, version, author, revealed: 0, plan: None, out_of_step: false })
+    }
+
+    // Hands the bot the game as it stands
+    pub fn start(&mut self, game: &Game) -> Result<(), TbpError> {
+        let start = start_state(game)?;
+        self.revealed = game.stats.pieces + start.queue.len();
+        self.plan = None;
+        self.out_of_step = false;
+        self.transport.send(&FrontendMessage::Start(start))
+    }
+
+    pub fn stop(&mut self) -> Result<(), TbpError> {
+        self.transport.send(&FrontendMessage::Stop)
+    }
+
+    /// The next input towards the bot's choice of placement, or None
+    /// while there's no piece to move.
+    pub fn next_action(&mut self, game: &Game) -> Result<Option<Action>, TbpError> {
+        if !is_moving(game) { return Ok(None) }
+
+        if let Some(plan) = self.plan.as_mut().filter(|plan| plan.is_for(game)) {
+            // if gravity has carried the piece past the bot's
+            // placement, it's left to fall where it will
+            let action = plan.next_action(game);
+            self.out_of_step |= action.is_none();
+            return Ok(action)
+        }
+        if self.out_of_step {
+            self.stop()?;
+            self.start(game)?;
+        }
+        self.reveal(game)?;
+
+        self.transport.send(&FrontendMessage::Suggest)?;
+        let moves = match receive(&mut self.transport)? {
+            BotMessage::Suggestion { moves } => moves,
+            BotMessage::Error { reason } => return Err(TbpError::Bot(reason)),
+            message => return Err(TbpError::Unexpected(message)),
+        };
+        let options = placements(&game.board, &game.current_piece);
+        let (mv, placement) = moves
+            .into_iter()
+            .find_map(|mv| {
+                let target = mv.location.cells();
+                let placement = options.iter().find(|option| cells(&option.piece) == target)?;
+                Some((mv, placement.clone()))
+            })
+            .ok_or(TbpError::NoMove)?;
+        self.transport.send(&FrontendMessage::Play { mv })?;
+        Ok(self.plan.insert(Plan::new(game, placement)).next_action(game))
+    }
+
+    // Tells the bot about the pieces that have come into the queue
+    fn reveal(&mut self, game: &Game) -> Result<(), TbpError> {
+        let queue = queue(game)?;
+        let revealed = game.stats.pieces + queue.len();
+        let new = revealed.saturating_sub(self.revealed).min(queue.len());
+        for piece in &queue[queue.len() - new..] {
+            self.transport.send(&FrontendMessage::NewPiece { piece: *piece })?;
+        }
+        self.revealed = revealed;
+        Ok(())
+    }
+}
+
+impl<T: Transport> Drop for Bot<T> {
+    fn drop(&mut self) {
+        let _ = self.transport.send(&FrontendMessage::Quit);
+    }
+}
+
+// Skips over the messages this version doesn't know
+fn receive<T: Transport>(transport: &mut T) -> Result<BotMessage, TbpError> {
+    loop {
+        match transport.receive()? {
+            BotMessage::Unknown => continue,
+            message => return Ok(message),
+        }
+    }
+}
+
+fn queue(game: &Game) -> Result<Vec<Tetromino>, TbpError> {
+    std::iter::once(&game.current_piece)
+        .chain(std::iter::once(&game.next_piece))
+        .chain(game.upcoming.iter())
+        .map(|piece| Tetromino::of(&piece.shape).ok_or(TbpError::Unsupported("piece sets besides the classic one")))
+        .collect()
+}
+
+// The falling piece isn't on the board; it's at the front of the queue.
+// Settled cells are all garbage to the bot, since the board doesn't
+// remember which piece left them.
+pub fn start_state(game: &Game) -> Result<Start, TbpError> {
+    if game.board.width() != BOARD_WIDTH {
+        return Err(TbpError::Unsupported("boards other than 10 wide"))
+    }
+    let board = (0..BOARD_HEIGHT)
+        .map(|y| {
+            (0..BOARD_WIDTH)
+                .map(|x| {
+                    let cell = game.board.get(x, y)?;
+                    (cell.value == Color::Black).then_some('G')
+                })
+                .collect()
+        })
+        .collect();
+    Ok(Start {
+        hold: None,
+        queue: queue(game)?,
+        combo: 0,
+        back_to_back: false,
+        board,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, collections::VecDeque, rc::Rc};
+    use super::*;
+    use crate::config::Config;
+
+    // A bot in the same process, which drops each piece as low as it'll go
+    #[derive(Default)]
+    struct MockBot {
+        board: Vec<Vec<bool>>,
+        queue: VecDeque<Tetromino>,
+        outbox: VecDeque<BotMessage>,
+        played: Vec<Move>,
+    }
+
+    impl MockBot {
+        fn fits(&self, cells: &[Point]) -> bool {
+            cells.iter().all(|p| {
+                (0..BOARD_WIDTH as i32).contains(&p.x)
+                    && (0..BOARD_HEIGHT as i32).contains(&p.y)
+                    && !self.board[p.y as usize][p.x as usize]
+            })
+        }
+
+        // Every spot the piece can be dropped into from above, lowest first
+        fn suggest(&self) -> Vec<Move> {
+            let piece = self.queue[0];
+            let mut moves = vec![];
+            for orientation in [Orientation::North, Orientation::East, Orientation::South, Orientation::West] {
+                for x in 0..BOARD_WIDTH as i32 {
+                    let mut location = Location { piece, orientation, x, y: BOARD_HEIGHT as i32 - 3 };
+                    if !self.fits(&location.cells()) { continue }
+                    while self.fits(&Location { y: location.y - 1, ..location }.cells()) {
+                        location.y -= 1;
+                    }
+                    moves.push(Move { location, spin: Spin::None });
+                }
+            }
+            moves.sort_by_key(|mv| mv.location.cells().iter().map(|p| p.y).max());
+            moves
+        }
+
+        fn play(&mut self, mv: Move) {
+            for p in mv.location.cells() {
+                self.board[p.y as usize][p.x as usize] = true;
+            }
+            self.board.retain(|row| !row.iter().all(|cell| *cell));
+            self.board.resize(BOARD_HEIGHT, vec![false; BOARD_WIDTH]);
+            self.queue.pop_front();
+            self.played.push(mv);
+        }
+    }
+
+    struct Mock(Rc<RefCell<MockBot>>);
+
+    impl Transport for Mock {
+        fn send(&mut self, message: &FrontendMessage) -> Result<(), TbpError> {
+            // everything goes through JSON, as it would to a real bot
+            let line = serde_json::to_string(message).unwrap();
+            let mut bot = self.0.borrow_mut();
+            match serde_json::from_str(&line).unwrap() {
+                FrontendMessage::Rules => bot.outbox.push_back(BotMessage::Ready),
+                FrontendMessage::Start(start) => {
+                    bot.board = start.board
+                        .iter()
+                        .map(|row| row.iter().map(Option::is_some).collect())
+                        .collect();
+                    bot.queue = start.queue.into();
+                },
+                FrontendMessage::Suggest => {
+                    let moves = bot.suggest();
+                    bot.outbox.push_back(BotMessage::Suggestion { moves });
+                },
+                FrontendMessage::Play { mv } => bot.play(mv),
+                FrontendMessage::NewPiece { piece } => bot.queue.push_back(piece),
+                FrontendMessage::Stop | FrontendMessage::Quit => {},
+            }
+            Ok(())
+        }
+
+        fn receive(&mut self) -> Result<BotMessage, TbpError> {
+            let line = serde_json::to_string(&self.0.borrow_mut().outbox.pop_front().ok_or(TbpError::Closed)?).unwrap();
+            serde_json::from_str(&line).map_err(TbpError::Format)
+        }
+    }
+
+    fn mock() -> Rc<RefCell<MockBot>> {
+        let bot = MockBot {
+            outbox: VecDeque::from([
+                BotMessage::Unknown,
+                BotMessage::Info {
+                    name: String::from("Mock"),
+                    version: String::from("1"),
+                    author: String::new(),
+                    features: vec![],
+                },
+            ]),
+            ..MockBot::default()
+        };
+        Rc::new(RefCell::new(bot))
+    }
+
+    #[test]
+    fn test_messages() {
+        let game = Game::new(Config { seed: Some(3), preview: 3, ..Config::default() });
+        let start = serde_json::to_value(FrontendMessage::Start(start_state(&game).unwrap())).unwrap();
+        assert_eq!(start["type"], "start");
+        assert_eq!(start["hold"], serde_json::Value::Null);
+        assert_eq!(start["queue"].as_array().unwrap().len(), 4);
+        assert_eq!(start["board"].as_array().unwrap().len(), 40);
+        assert_eq!(start["board"][0].as_array().unwrap().len(), 10);
+
+        let suggestion: BotMessage = serde_json::from_str(r#"{
+            "type": "suggestion",
+            "moves": [{"location": {"type": "T", "orientation": "east", "x": 4, "y": 1}, "spin": "none"}],
+            "move_info": {"nodes": 1000}
+        }"#).unwrap();
+        let location = Location { piece: Tetromino::T, orientation: Orientation::East, x: 4, y: 1 };
+        assert_eq!(suggestion, BotMessage::Suggestion { moves: vec![Move { location, spin: Spin::None }] });
+        assert_eq!(
+            location.cells(),
+            [Point::new(4, 0), Point::new(4, 1), Point::new(4, 2), Point::new(5, 1)],
+        );
+        let unknown: BotMessage = serde_json::from_str(r#"{"type": "something_new", "x": 1}"#).unwrap();
+        assert_eq!(unknown, BotMessage::Unknown);
+        assert_eq!(serde_json::to_string(&FrontendMessage::Suggest).unwrap(), r#"{"type":"suggest"}"#);
+    }
+
+    #[test]
+    fn test_bot_plays() {
+        let state = mock();
+        let mut game = Game::new(Config { seed: Some(9), ..Config::default() });
+        let mut bot = Bot::connect(Mock(state.clone())).unwrap();
+        assert_eq!(bot.name, "Mock");
+        bot.start(&game).unwrap();
+
+        while game.stats.pieces < 30 && !game.game_over {
+            let pieces = game.stats.pieces;
+            if let Some(action) = bot.next_action(&game).unwrap() {
+                game.on_action(action);
+            }
+            game.on_tick();
+            if game.stats.pieces == pieces { continue }
+            // the bot's idea of the board keeps up with the game's
+            let bot_board = &state.borrow().board;
+            for (y, row) in bot_board.iter().enumerate().take(game.board.height()) {
+                for (x, filled) in row.iter().enumerate() {
+                    let settled = game.board.get(x, y).unwrap().value == Color::Black;
+                    assert_eq!(*filled, settled, "{x}, {y} after {} pieces", game.stats.pieces);
+                }
+            }
+        }
+        assert_eq!(state.borrow().played.len(), 30);
+        assert!(game.stats.rows_cleared > 0);
+    }
+
+    #[test]
+    fn test_connect_errors() {
+        let connect = |first| {
+            let bot = MockBot { outbox: VecDeque::from([first]), ..MockBot::default() };
+            Bot::connect(Mock(Rc::new(RefCell::new(bot)))).err()
+        };
+        let reason = String::from("no");
+        assert!(matches!(connect(BotMessage::Error { reason }), Some(TbpError::Bot(_))));
+        assert!(matches!(connect(BotMessage::Ready), Some(TbpError::Unexpected(BotMessage::Ready))));
+        assert!(matches!(Process::spawn(" "), Err(TbpError::NoCommand)));
+    }
+
+    #[test]
+    fn test_unsupported() {
+        let mut game = Game::new(Config { board_width: Some(12), ..Config::default() });
+        assert!(matches!(start_state(&game), Err(TbpError::Unsupported(_))));
+        game.board = crate::game::matrix::Matrix::empty();
+        assert!(start_state(&game).is_ok());
+    }
+}
@@ -1,6 +1,62 @@
 mod draw_game;
 mod draw_config;
+mod draw_high_scores;
+mod draw_versus;
+#[cfg(test)]
+mod snapshot;
 
-pub use draw_game::draw;
+use ratatui::{
+    backend::Backend,
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    style::{Color as TuiColor, Modifier, Style},
+    widgets::{Paragraph, Wrap},
+    Frame,
+};
+
+pub use draw_game::{
+    board_widget,
+    draw,
+    level_widget,
+    lines_widget,
+    preview_widget,
+    score_widget,
+    Hud,
+};
 pub use draw_config::draw_config;
+pub use draw_high_scores::{draw_high_scores, draw_name_entry};
+pub use draw_versus::draw_versus;
+
+// Shown instead of a screen that doesn't fit, rather than drawing it mangled
+fn draw_too_small<B: Backend>(f: &mut Frame<B>, width: u16, height: u16) {
+    let size = f.size();
+    let message = format!(
+        "Terminal too small\nneed {width}x{height}, have {}x{}",
+        size.width,
+        size.height,
+    );
+    let text = Paragraph::new(message)
+        .alignment(Alignment::Center)
+        .wrap(Wrap { trim: true })
+        .style(Style::default().add_modifier(Modifier::BOLD).fg(TuiColor::Yellow));
+    f.render_widget(text, centered(size, size.width, 2));
+}
 
+// A width by height area in the middle of `area`, cut down to fit
+fn centered(area: Rect, width: u16, height: u16) -> Rect {
+    let vertical = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(area.height.saturating_sub(height) / 2),
+            Constraint::Length(height.min(area.height)),
+            Constraint::Min(0),
+        ].as_ref())
+        .split(area);
+    Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Length(area.width.saturating_sub(width) / 2),
+            Constraint::Length(width.min(area.width)),
+            Constraint::Min(0),
+        ].as_ref())
+        .split(vertical[1])[1]
+}
@@ -6,23 +6,51 @@ use ratatui::{
         Block,
         Borders,
         BorderType,
+        Cell as TuiCell,
         List,
         ListItem,
         Padding,
         Paragraph,
+        Row,
+        Table,
     },
     Frame,
 };
 
-use crate::config::{ConfigBuilder, GameMode, Section};
+use crate::{
+    config::{ConfigBuilder, GameMode, Section},
+    game::action::Action,
+    input::key_name,
+};
+use super::draw_high_scores;
 
 const BOARD_WIDTH: u16 = 22;
 const BOARD_HEIGHT: u16 = 22;
-const LEFT_WIDGET_WIDTH: u16 = 22;
-const STATS_HEIGHT: u16 = BOARD_HEIGHT / 4;
+// the menu, plus the border around the screen
+const MIN_WIDTH: u16 = BOARD_WIDTH * 2 + 2;
+const MIN_HEIGHT: u16 = BOARD_HEIGHT + 2;
 
 pub fn draw_config<B: Backend>(f: &mut Frame<B>, config_builder: &mut ConfigBuilder) {
+    if config_builder.showing_high_scores {
+        draw_high_scores(
+            f,
+            &config_builder.high_scores,
+            config_builder.high_score_mode,
+            config_builder.high_score_level + 1,
+        );
+        return
+    }
+    if config_builder.showing_controls {
+        draw_controls(f, config_builder);
+        return
+    }
+
     let size = f.size();
+    // the buttons' labels disappear into their padding if the menu is squeezed
+    if size.width < MIN_WIDTH || size.height < MIN_HEIGHT {
+        super::draw_too_small(f, MIN_WIDTH, MIN_HEIGHT);
+        return
+    }
     let margin = (size.width - BOARD_WIDTH * 2) / 2;
     let vertical_margin = (size.height - BOARD_HEIGHT) / 2;
 
@@ -60,9 +88,108 @@ pub fn draw_config<B: Backend>(f: &mut Frame<B>, config_builder: &mut ConfigBuil
         ].as_ref())
         .split(center_chunks[1]);
 
+    let list_chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Percentage(50),
+            Constraint::Percentage(50),
+        ].as_ref())
+        .split(widget_chunks[1]);
+
+    let buttons = if config_builder.saved_game.is_some() { 4 } else { 3 };
+    let button_constraints = vec![Constraint::Ratio(1, buttons); buttons as usize];
+    let button_chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints(button_constraints)
+        .split(widget_chunks[2]);
+
     render_game_type_widget(config_builder, widget_chunks[0], f);
-    render_choose_level_widget(config_builder, widget_chunks[1], f);
-    render_start(config_builder, widget_chunks[2], f);
+    render_choose_level_widget(config_builder, list_chunks[0], f);
+    render_choose_piece_set_widget(config_builder, list_chunks[1], f);
+    let buttons = if config_builder.saved_game.is_some() {
+        render_resume(config_builder, button_chunks[0], f);
+        &button_chunks[1..]
+    } else {
+        &button_chunks[..]
+    };
+    render_start(config_builder, buttons[0], f);
+    render_controls_button(config_builder, buttons[1], f);
+    render_high_scores_button(config_builder, buttons[2], f);
+}
+
+// The key for each action, with the preset they match above
+fn draw_controls<B: Backend>(f: &mut Frame<B>, config_builder: &ConfigBuilder) {
+    let size = f.size();
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title("TTrYs")
+        .title_alignment(Alignment::Center)
+        .border_type(BorderType::Double);
+    f.render_widget(block, size);
+
+    let target = super::centered(size, BOARD_WIDTH * 2, Action::all().len() as u16 + 8);
+    f.render_widget(controls_widget(config_builder), target);
+
+    // a line of help along the bottom, inside the border
+    let hint_area = Rect {
+        x: target.x + 2,
+        y: target.bottom().saturating_sub(2),
+        width: target.width.saturating_sub(4),
+        height: 1,
+    };
+    f.render_widget(controls_hint(config_builder), hint_area);
+}
+
+fn controls_widget(config_builder: &ConfigBuilder) -> Table<'static> {
+    let controls = &config_builder.controls;
+    let base_style = Style::default().fg(TuiColor::Indexed(45));
+    let selected_style = base_style.add_modifier(Modifier::BOLD | Modifier::REVERSED);
+    let preset = controls.preset().map_or("Custom", |preset| preset.name());
+    let preset_row = Row::new(vec![
+        TuiCell::from("Preset"),
+        TuiCell::from(format!("< {preset} >")),
+    ]);
+    let action_rows = Action::all().into_iter().map(|action| {
+        let keys = if controls.waiting == Some(action) {
+            String::from("press a key...")
+        } else {
+            controls.keys
+                .keys_for(action)
+                .iter()
+                .map(|key| key_name(*key))
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        Row::new(vec![TuiCell::from(action.label()), TuiCell::from(keys)])
+    });
+    let rows = std::iter::once(preset_row)
+        .chain(action_rows)
+        .enumerate()
+        .map(|(i, row)| {
+            let style = if i == controls.selected { selected_style } else { base_style };
+            row.style(style).bottom_margin(u16::from(i == 0))
+        })
+        .collect::<Vec<_>>();
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_type(BorderType::Thick)
+        .padding(Padding { left: 1, right: 1, top: 1, bottom: 0 })
+        .title("Controls")
+        .title_alignment(Alignment::Center)
+        .style(base_style);
+    Table::new(rows)
+        .block(block)
+        .widths(&[Constraint::Length(24), Constraint::Length(16)])
+}
+
+fn controls_hint(config_builder: &ConfigBuilder) -> Paragraph<'static> {
+    let controls = &config_builder.controls;
+    let hint = match (&controls.message, controls.is_waiting()) {
+        (Some(message), _) => message.clone(),
+        (None, true) => String::from("Esc keeps the current key"),
+        (None, false) => String::from("Enter to rebind, Esc to go back"),
+    };
+    Paragraph::new(hint).style(Style::default().fg(TuiColor::Indexed(245)))
 }
 
 fn render_game_type_widget<B: Backend>(config_builder: &mut ConfigBuilder, target: Rect, f: &mut Frame<B>) {
@@ -148,6 +275,31 @@ fn render_choose_level_widget<B: Backend>(config_builder: &mut ConfigBuilder, ta
     f.render_stateful_widget(items, target, &mut config_builder.level_list.state);
 }
 
+fn render_choose_piece_set_widget<B: Backend>(config_builder: &mut ConfigBuilder, target: Rect, f: &mut Frame<B>) {
+    let base_style = Style::default().fg(TuiColor::Indexed(141));
+    let (border_type, style) = if config_builder.current_section == Section::ChoosePieceSet {
+        (BorderType::Thick, base_style.add_modifier(Modifier::BOLD))
+    } else {
+        (BorderType::Plain, base_style)
+    };
+    let container = Block::default()
+        .borders(Borders::ALL)
+        .border_type(border_type)
+        .style(style)
+        .title("Pieces");
+    let items: Vec<ListItem> = config_builder
+        .piece_set_list
+        .items
+        .iter()
+        .map(|i| ListItem::new(i.as_str()).style(base_style))
+        .collect();
+    let items = List::new(items)
+        .block(container)
+        .highlight_symbol("> ");
+
+    f.render_stateful_widget(items, target, &mut config_builder.piece_set_list.state);
+}
+
 fn render_start<B: Backend>(config_builder: &mut ConfigBuilder, target: Rect, f: &mut Frame<B>) {
     let (border_type, text_style) = if config_builder.current_section == Section::StartGame {
         (BorderType::Thick, Style::default().add_modifier(Modifier::BOLD))
@@ -161,9 +313,207 @@ fn render_start<B: Backend>(config_builder: &mut ConfigBuilder, target: Rect, f:
         .borders(Borders::ALL)
         .border_type(border_type)
         .style(style);
-    let start = Paragraph::new("Start")
+    // left and right switch to watching the AI play, training or versus
+    let start = Paragraph::new(config_builder.start_mode.label())
         .alignment(Alignment::Center)
         .block(block)
         .style(text_style);
     f.render_widget(start, target);
 }
+
+fn render_resume<B: Backend>(config_builder: &mut ConfigBuilder, target: Rect, f: &mut Frame<B>) {
+    let (border_type, text_style) = if config_builder.current_section == Section::Resume {
+        (BorderType::Thick, Style::default().add_modifier(Modifier::BOLD))
+    } else {
+        (BorderType::Plain, Style::default())
+    };
+    let style = Style::default()
+        .fg(TuiColor::Indexed(45));
+    let block = Block::default()
+        .padding(Padding { left: 0, right: 0, top: 2, bottom: 2 })
+        .borders(Borders::ALL)
+        .border_type(border_type)
+        .style(style);
+    let resume = Paragraph::new("Resume")
+        .alignment(Alignment::Center)
+        .block(block)
+        .style(text_style);
+    f.render_widget(resume, target);
+}
+
+fn render_controls_button<B: Backend>(config_builder: &mut ConfigBuilder, target: Rect, f: &mut Frame<B>) {
+    let (border_type, text_style) = if config_builder.current_section == Section::Controls {
+        (BorderType::Thick, Style::default().add_modifier(Modifier::BOLD))
+    } else {
+        (BorderType::Plain, Style::default())
+    };
+    let style = Style::default()
+        .fg(TuiColor::Indexed(141));
+    let block = Block::default()
+        .padding(Padding { left: 0, right: 0, top: 2, bottom: 2 })
+        .borders(Borders::ALL)
+        .border_type(border_type)
+        .style(style);
+    let button = Paragraph::new("Controls")
+        .alignment(Alignment::Center)
+        .block(block)
+        .style(text_style);
+    f.render_widget(button, target);
+}
+
+fn render_high_scores_button<B: Backend>(config_builder: &mut ConfigBuilder, target: Rect, f: &mut Frame<B>) {
+    let (border_type, text_style) = if config_builder.current_section == Section::HighScores {
+        (BorderType::Thick, Style::default().add_modifier(Modifier::BOLD))
+    } else {
+        (BorderType::Plain, Style::default())
+    };
+    let style = Style::default()
+        .fg(TuiColor::Indexed(220));
+    let block = Block::default()
+        .padding(Padding { left: 0, right: 0, top: 2, bottom: 2 })
+        .borders(Borders::ALL)
+        .border_type(border_type)
+        .style(style);
+    // squeezed in beside the Resume button
+    let label = if target.width < 13 { "Scores" } else { "High Scores" };
+    let button = Paragraph::new(label)
+        .alignment(Alignment::Center)
+        .block(block)
+        .style(text_style);
+    f.render_widget(button, target);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        config::{Config, StartMode},
+        game::{piece_set::PieceSet, Game},
+        high_scores::HighScores,
+        settings::Settings,
+        ui::snapshot::{assert_snapshot, render},
+    };
+
+    // Without whatever saved game and high scores are on this machine
+    fn config_builder() -> ConfigBuilder<'static> {
+        let mut config_builder = ConfigBuilder::new(Settings::default());
+        config_builder.saved_game = None;
+        config_builder.current_section = Section::default();
+        config_builder.high_scores = HighScores::default();
+        // nor any piece sets installed here
+        let built_in = PieceSet::built_in().len();
+        config_builder.piece_sets.truncate(built_in);
+        config_builder.piece_set_list.items.truncate(built_in + 1);
+        config_builder
+    }
+
+    fn snapshot(name: &str, config_builder: &mut ConfigBuilder) {
+        assert_snapshot(name, &render(80, 24, |f| draw_config(f, config_builder)));
+    }
+
+    #[test]
+    fn test_sections() {
+        let mut config_builder = config_builder();
+        config_builder.saved_game = Some(Game::new(Config { seed: Some(1), ..Config::default() }));
+        let sections = [
+            (Section::ChooseGameMode, "game_mode"),
+            (Section::ChooseInitialLevel, "initial_level"),
+            (Section::ChoosePieceSet, "piece_set"),
+            (Section::Resume, "resume"),
+            (Section::StartGame, "start_game"),
+            (Section::Controls, "controls"),
+            (Section::HighScores, "high_scores"),
+        ];
+        for (section, name) in sections {
+            config_builder.current_section = section;
+            snapshot(&format!("config_section_{name}"), &mut config_builder);
+        }
+    }
+
+    #[test]
+    fn test_choices() {
+        let mut config_builder = config_builder();
+        config_builder.on_right();
+        config_builder.next_section();
+        (0..4).for_each(|_| config_builder.on_right());
+        config_builder.next_section();
+        config_builder.on_left();
+        snapshot("config_choices", &mut config_builder);
+    }
+
+    #[test]
+    fn test_ai_plays() {
+        let mut config_builder = config_builder();
+        config_builder.current_section = Section::StartGame;
+        assert!(config_builder.build().finesse);
+        config_builder.on_right();
+        assert_eq!(config_builder.start_mode, StartMode::AiPlays);
+        assert!(!config_builder.build().finesse);
+        snapshot("config_ai_plays", &mut config_builder);
+
+        config_builder.on_right();
+        assert_eq!(config_builder.start_mode, StartMode::Training);
+        assert!(config_builder.build().training);
+        config_builder.on_right();
+        assert_eq!(config_builder.start_mode, StartMode::Versus);
+        config_builder.on_right();
+        assert_eq!(config_builder.start_mode, StartMode::Play);
+        config_builder.on_left();
+        assert_eq!(config_builder.start_mode, StartMode::Versus);
+    }
+
+    #[test]
+    fn test_browsing_high_scores() {
+        let mut config_builder = config_builder();
+        config_builder.current_section = Section::HighScores;
+        config_builder.configured();
+        config_builder.next_section();
+        config_builder.on_left();
+        assert_eq!((config_builder.high_score_mode, config_builder.high_score_level), (GameMode::BType, 9));
+        // the game that starts is left as it was
+        let config = config_builder.build();
+        assert_eq!((config.game_mode, config.initial_level), (GameMode::AType, 0));
+        assert_eq!(config_builder.settings(), Settings::default());
+    }
+
+    #[test]
+    fn test_command_line_choices_are_not_saved() {
+        let saved = Settings { level: 3, ..Settings::default() };
+        let run = Settings { level: 7, theme: Some(2), ..saved.clone() };
+        let mut config_builder = ConfigBuilder::new(run);
+        assert_eq!(config_builder.changed_settings(&saved), saved);
+
+        config_builder.current_section = Section::ChooseGameMode;
+        config_builder.on_right();
+        let changed = config_builder.changed_settings(&saved);
+        assert_eq!(changed, Settings { game_mode: GameMode::BType, ..saved });
+        assert_eq!(config_builder.build().initial_level, 6);
+    }
+
+    #[test]
+    fn test_controls_screen() {
+        let mut config_builder = config_builder();
+        config_builder.current_section = Section::Controls;
+        config_builder.configured();
+        snapshot("config_controls_screen", &mut config_builder);
+        config_builder.on_right();
+        config_builder.next_section();
+        config_builder.next_section();
+        config_builder.configured();
+        snapshot("config_controls_waiting", &mut config_builder);
+    }
+
+    #[test]
+    fn test_sizes() {
+        let mut config_builder = config_builder();
+        for (width, height) in [(80, 24), (46, 24), (45, 24), (80, 23)] {
+            let screen = render(width, height, |f| draw_config(f, &mut config_builder));
+            assert_snapshot(&format!("config_{width}x{height}"), &screen);
+        }
+        for width in (0..=90).step_by(5) {
+            for height in (0..=30).step_by(3) {
+                render(width, height, |f| draw_config(f, &mut config_builder));
+            }
+        }
+    }
+}
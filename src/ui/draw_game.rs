@@ -8,6 +8,7 @@ use ratatui::{
         Borders,
         BorderType,
         Cell as TuiCell,
+        Clear,
         Padding,
         Paragraph,
         Row,
@@ -16,18 +17,28 @@ use ratatui::{
     Frame,
 };
 
-use crate::game::{
-    Game,
-    Stats,
-    matrix::{Color, Matrix, Cell},
-    piece::Piece,
-    level::Level,
+use crate::{
+    ai::Hint,
+    game::{
+        Game,
+        Phase,
+        Stats,
+        action::Action,
+        matrix::{Color, Matrix, Cell},
+        piece::{Piece, Point},
+        level::Level,
+    },
+    input::{key_name, KeyBindings, HELP_KEY, LEGEND_KEY, QUIT_KEY},
+    pause_menu::{PauseItem, PauseMenu},
 };
 
-const BOARD_WIDTH: u16 = 22;
 const BOARD_HEIGHT: u16 = 22;
-const LEFT_WIDGET_WIDTH: u16 = 22;
-const STATS_HEIGHT: u16 = BOARD_HEIGHT / 4;
+pub(super) const LEFT_WIDGET_WIDTH: u16 = 22;
+pub(super) const STATS_HEIGHT: u16 = BOARD_HEIGHT / 4;
+const HELP_WIDTH: u16 = 48;
+pub(super) const COMPACT_WIDGET_WIDTH: u16 = 12;
+// the border around the whole screen
+pub(super) const FRAME: u16 = 2;
 const TTRYS: &str = r#"
 _____ _____  __   __
 |_   _|_   _| \ \ / /__
@@ -50,10 +61,53 @@ const PAUSED: &str = r#"
 
 "#;
 
-pub fn draw<B: Backend>(f: &mut Frame<B>, game: &mut Game) {
+/// What's drawn around the game besides the game itself
+#[derive(Default)]
+pub struct Hud<'a> {
+    // the controls legend is left out without them, as in a replay
+    pub keys: Option<&'a KeyBindings>,
+    pub show_legend: bool,
+    pub show_help: bool,
+    // shown while the game is paused
+    pub pause_menu: Option<&'a PauseMenu>,
+    // where the AI would put the piece, if hints are on
+    pub hint: Option<&'a Hint>,
+}
+
+// How much of the game fits on screen
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Arrangement {
+    // the stats, the board and the column on its right
+    Full,
+    // the queue and legend left out
+    NoRight,
+    // one column per cell and a narrow stats column
+    Compact,
+    TooSmall,
+}
+
+/// The whole game screen, laid out to fit the frame
+pub fn draw<B: Backend>(f: &mut Frame<B>, game: &mut Game, hud: &Hud) {
     let size = f.size();
-    let margin = (size.width - LEFT_WIDGET_WIDTH - BOARD_WIDTH) / 2;
-    let vertical_margin = (size.height - BOARD_HEIGHT) / 2;
+    let board_height = game.board.height() as u16 + 2;
+    // the rest of the preview queue and the controls legend go
+    // in a column right of the board, across from the stats
+    let right_width = if game.upcoming.is_empty() && hud.keys.is_none() { 0 } else { LEFT_WIDGET_WIDTH };
+    let arrangement = arrange(size, game, right_width);
+    let (side_width, cell_width, right_width) = match arrangement {
+        Arrangement::Full => (LEFT_WIDGET_WIDTH, 2, right_width),
+        Arrangement::NoRight => (LEFT_WIDGET_WIDTH, 2, 0),
+        Arrangement::Compact => (COMPACT_WIDGET_WIDTH, 1, 0),
+        Arrangement::TooSmall => {
+            let (width, height) = minimum_size(game);
+            super::draw_too_small(f, width, height);
+            return
+        },
+    };
+    // one or two columns per cell, plus borders
+    let board_width = game.board.width() as u16 * cell_width + 2;
+    let margin = (size.width - side_width - board_width - right_width) / 2;
+    let vertical_margin = (size.height - board_height) / 2;
 
     let block = Block::default()
         .borders(Borders::ALL)
@@ -67,7 +121,7 @@ pub fn draw<B: Backend>(f: &mut Frame<B>, game: &mut Game) {
         .direction(Direction::Vertical)
         .constraints([
             Constraint::Length(vertical_margin),
-            Constraint::Length(BOARD_HEIGHT),
+            Constraint::Length(board_height),
             Constraint::Length(vertical_margin),
         ].as_ref())
         .split(size);
@@ -76,35 +130,214 @@ pub fn draw<B: Backend>(f: &mut Frame<B>, game: &mut Game) {
         .direction(Direction::Horizontal)
         .constraints([
             Constraint::Length(margin),
-            Constraint::Length(LEFT_WIDGET_WIDTH),
-            Constraint::Length(BOARD_WIDTH),
+            Constraint::Length(side_width),
+            Constraint::Length(board_width),
+            Constraint::Length(right_width),
             Constraint::Length(margin),
         ].as_ref())
         .split(chunks[1]);
 
-    let stats_chunks = Layout::default()
-        .direction(Direction::Vertical)
-        .constraints([
-            Constraint::Length(STATS_HEIGHT),
-            Constraint::Length(STATS_HEIGHT),
-            Constraint::Length(STATS_HEIGHT),
-            Constraint::Length(STATS_HEIGHT),
-            Constraint::Min(0),
-        ].as_ref())
-        .split(center_chunks[1]);
-
-    draw_game_board(f, center_chunks[2], game);
-    draw_stats_widgets(f, stats_chunks, game);
+    // a hint only lasts as long as the piece it's for. In training the
+    // game's own target is shown instead.
+    let hint = game.target.as_ref().or(hud.hint).filter(|hint| hint.is_for(game) && game.phase == Phase::Falling && !game.game_over);
+    draw_game_board(f, center_chunks[2], game, hint, cell_width);
+    if arrangement == Arrangement::Compact {
+        draw_compact_stats(f, center_chunks[1], game);
+    } else {
+        let stats_chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(STATS_HEIGHT),
+                Constraint::Length(STATS_HEIGHT),
+                Constraint::Length(STATS_HEIGHT),
+                Constraint::Length(STATS_HEIGHT),
+                Constraint::Min(0),
+            ].as_ref())
+            .split(center_chunks[1]);
+        draw_stats_widgets(f, stats_chunks, game);
+    }
+    if arrangement == Arrangement::Full {
+        let right_chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(STATS_HEIGHT * game.upcoming.len() as u16),
+                Constraint::Min(0),
+            ].as_ref())
+            .split(center_chunks[3]);
+        draw_queue(f, right_chunks[0], game);
+        if let Some(keys) = hud.keys {
+            draw_legend(f, right_chunks[1], keys, hud.show_legend);
+        }
+    }
     draw_top_banner(f, chunks[0], game);
+    if let Some(hint) = hint {
+        draw_hint_keys(f, chunks[2], game, hint, hud.keys);
+    }
+    if let (Some(keys), true) = (hud.keys, hud.show_help) {
+        draw_help(f, keys);
+    } else if let (Some(menu), true) = (hud.pause_menu, game.paused && !game.game_over) {
+        draw_pause_menu(f, menu, game);
+    }
 }
 
-fn draw_game_board<B: Backend>(f: &mut Frame<B>, target: Rect, game: &Game) {
-    let board = board_widget(&game.board);
+// The widest arrangement that fits. The board always needs its full
+// height, so a terminal too short for it is too small whatever its width.
+fn arrange(size: Rect, game: &Game, right_width: u16) -> Arrangement {
+    let columns = game.board.width() as u16;
+    let full = FRAME + LEFT_WIDGET_WIDTH + columns * 2 + 2;
+    let (min_width, min_height) = minimum_size(game);
+    if size.height < min_height || size.width < min_width {
+        Arrangement::TooSmall
+    } else if size.width >= full + right_width {
+        Arrangement::Full
+    } else if size.width >= full {
+        Arrangement::NoRight
+    } else {
+        Arrangement::Compact
+    }
+}
+
+// What the compact arrangement needs
+fn minimum_size(game: &Game) -> (u16, u16) {
+    let (columns, rows) = (game.board.width() as u16, game.board.height() as u16);
+    (FRAME + COMPACT_WIDGET_WIDTH + columns + 2, FRAME + rows + 2)
+}
+
+pub(super) fn draw_game_board<B: Backend>(f: &mut Frame<B>, target: Rect, game: &Game, hint: Option<&Hint>, cell_width: u16) {
+    let ghost = if game.show_ghost && !game.game_over && game.phase == Phase::Falling {
+        Some(game.ghost_piece())
+    } else {
+        None
+    };
+    // the rows being cleared dissolve from the middle outwards
+    let (clearing, dissolved) = match &game.phase {
+        Phase::Clearing { rows, frames_left } => {
+            let frames = game.line_clear_frames.max(1);
+            let half = game.board.width().div_ceil(2);
+            (rows.as_slice(), half * frames.saturating_sub(*frames_left) / frames)
+        },
+        _ => (&[][..], 0),
+    };
+    let board = board_widget(
+        &game.board,
+        ghost.as_ref(),
+        game.piece_color(&game.current_piece),
+        hint.map(|hint| &hint.piece),
+        cell_width,
+        clearing,
+        dissolved,
+    );
     f.render_widget(board, target);
 }
 
+fn draw_queue<B: Backend>(f: &mut Frame<B>, target: Rect, game: &Game) {
+    if game.upcoming.is_empty() { return }
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(vec![Constraint::Length(STATS_HEIGHT); game.upcoming.len()])
+        .split(target);
+    game.upcoming
+        .iter()
+        .zip(chunks.iter())
+        .for_each(|(piece, chunk)| f.render_widget(preview_widget(game, Some(piece), "", 2), *chunk));
+}
+
+fn draw_legend<B: Backend>(f: &mut Frame<B>, target: Rect, keys: &KeyBindings, expanded: bool) {
+    if expanded {
+        f.render_widget(legend_widget(keys), target);
+    } else {
+        let collapsed = Rect { height: target.height.min(3), ..target };
+        f.render_widget(collapsed_legend_widget(), collapsed);
+    }
+}
+
+fn draw_help<B: Backend>(f: &mut Frame<B>, keys: &KeyBindings) {
+    let height = Action::all().len() as u16 + 10;
+    let target = super::centered(f.size(), HELP_WIDTH, height);
+    f.render_widget(Clear, target);
+    f.render_widget(help_widget(keys), target);
+}
+
+// The keys for the hint, on the line under the board if there's room
+fn draw_hint_keys<B: Backend>(f: &mut Frame<B>, target: Rect, game: &Game, hint: &Hint, keys: Option<&KeyBindings>) {
+    if target.height < 2 { return }
+    let line = Rect { height: 1, ..target };
+    let mut text = hint_text(&hint.keys, keys);
+    if game.training {
+        text.push_str(&format!("  Faults: {}", game.stats.finesse_faults));
+    }
+    let text = Paragraph::new(text)
+        .alignment(Alignment::Center)
+        .style(Style::default().fg(TuiColor::Indexed(51)));
+    f.render_widget(text, line);
+}
+
+// Runs of the same key are counted, and soft drops held
+fn hint_text(actions: &[Action], keys: Option<&KeyBindings>) -> String {
+    let mut runs: Vec<(Action, usize)> = vec![];
+    for action in actions {
+        match runs.last_mut() {
+            Some((last, count)) if last == action => *count += 1,
+            _ => runs.push((*action, 1)),
+        }
+    }
+    let presses: Vec<String> = runs
+        .into_iter()
+        .map(|(action, count)| {
+            let name = keys
+                .and_then(|keys| keys.keys_for(action).first())
+                .map_or_else(|| short_label(action).to_string(), |key| key_name(*key));
+            match (action, count) {
+                (Action::SoftDrop, _) => format!("hold {name}"),
+                (_, 1) => name,
+                (_, count) => format!("{name} x{count}"),
+            }
+        })
+        .collect();
+    format!("Hint: {}", presses.join(", "))
+}
+
+fn draw_pause_menu<B: Backend>(f: &mut Frame<B>, menu: &PauseMenu, game: &Game) {
+    let target = super::centered(f.size(), 22, PauseItem::all().len() as u16 + 4);
+    f.render_widget(Clear, target);
+    f.render_widget(pause_menu_widget(menu, game), target);
+}
+
+fn pause_menu_widget(menu: &PauseMenu, game: &Game) -> Table<'static> {
+    let base_style = Style::default().fg(TuiColor::Yellow);
+    let rows = PauseItem::all()
+        .into_iter()
+        .enumerate()
+        .map(|(i, item)| {
+            let state = match menu.is_on(item, game) {
+                Some(true) => "On",
+                Some(false) => "Off",
+                None => "",
+            };
+            let style = if i == menu.selected {
+                base_style.add_modifier(Modifier::BOLD | Modifier::REVERSED)
+            } else {
+                base_style
+            };
+            Row::new(vec![item.label(), state]).style(style)
+        })
+        .collect::<Vec<_>>();
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_type(BorderType::Double)
+        .padding(Padding { left: 1, right: 1, top: 1, bottom: 0 })
+        .title("Paused")
+        .title_alignment(Alignment::Center)
+        .style(base_style);
+    Table::new(rows)
+        .block(block)
+        .widths(&[Constraint::Length(12), Constraint::Length(4)])
+}
+
 fn draw_stats_widgets<B: Backend>(f: &mut Frame<B>, stats_chunks: Rc<[Rect]>, game: &Game) {
-    let next_piece = next_piece_widget(game);
+    let shown = if game.preview > 0 { Some(&game.next_piece) } else { None };
+    let next_piece = preview_widget(game, shown, "Next Piece", 2);
     f.render_widget(next_piece, stats_chunks[0]);
 
     let score = score_widget(&game.stats);
@@ -117,24 +350,72 @@ fn draw_stats_widgets<B: Backend>(f: &mut Frame<B>, stats_chunks: Rc<[Rect]>, ga
     f.render_widget(lines, stats_chunks[3]);
 }
 
+// The next piece and the numbers, squeezed into a narrow column
+fn draw_compact_stats<B: Backend>(f: &mut Frame<B>, target: Rect, game: &Game) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(STATS_HEIGHT),
+            Constraint::Min(0),
+        ].as_ref())
+        .split(target);
+    let shown = if game.preview > 0 { Some(&game.next_piece) } else { None };
+    f.render_widget(preview_widget(game, shown, "Next", 1), chunks[0]);
+    f.render_widget(compact_stats_widget(game), chunks[1]);
+}
+
 fn draw_top_banner<B: Backend>(f: &mut Frame<B>, target: Rect, game: &Game) {
+    // too short to show anything but its border, which would cover the title
+    if target.height < 3 { return }
     f.render_widget(banner_widget(game), target);
 }
 
-fn board_widget(board: &Matrix<Cell>) -> Table {
+/// The board, a row of the table to each row of cells, drawn `cell_width`
+/// (1 or 2) characters wide. The ghost is outlined in the piece's color
+/// where the piece itself is filled in, and a hinted placement is marked
+/// out in the same color over it. Rows being cleared are lit up, less the
+/// `dissolved` cells either side of the middle.
+pub fn board_widget<'a>(
+    board: &'a Matrix<Cell>,
+    ghost: Option<&Piece>,
+    ghost_color: u8,
+    hint: Option<&Piece>,
+    cell_width: u16,
+    clearing: &[usize],
+    dissolved: usize,
+) -> Table<'a> {
+    let ghost_points = ghost.map_or(&[][..], |piece| piece.points.as_slice());
+    let hint_points = hint.map_or(&[][..], |piece| piece.points.as_slice());
+    let width = board.width();
     let rows = board
         .rows
         .iter()
+        .enumerate()
         .rev()
-        .map(|row| {
+        .map(|(y, row)| {
             let cells = row
                 .iter()
-                .map(|cell| {
-                    if cell.value == Color::White {
-                        TuiCell::from("  ")
+                .enumerate()
+                .map(move |(x, cell)| {
+                    if clearing.contains(&y) {
+                        // how far the cell is from the middle of the row
+                        let distance = (2 * x + 1).abs_diff(width) / 2;
+                        if distance < dissolved {
+                            empty_cell(cell_width)
+                        } else {
+                            clearing_cell(cell_width)
+                        }
+                    } else if cell.value == Color::White {
+                        let point = Point::new(x as i32, y as i32);
+                        if hint_points.contains(&point) {
+                            hint_cell(ghost_color, cell_width)
+                        } else if ghost_points.contains(&point) {
+                            ghost_cell(ghost_color, cell_width)
+                        } else {
+                            empty_cell(cell_width)
+                        }
                     } else {
-                        TuiCell::from("[]")
-                            .style(Style::default().bg(cell_color(cell)))
+                        filled_cell(cell, cell_width)
                     }
                 });
             Row::new(cells).height(1)
@@ -145,59 +426,158 @@ fn board_widget(board: &Matrix<Cell>) -> Table {
 
     Table::new(rows)
         .block(table_block)
-        .widths(&[
-            Constraint::Length(2),
-            Constraint::Length(2),
-            Constraint::Length(2),
-            Constraint::Length(2),
-            Constraint::Length(2),
-            Constraint::Length(2),
-            Constraint::Length(2),
-            Constraint::Length(2),
-            Constraint::Length(2),
-            Constraint::Length(2),
-        ])
+        .widths(cell_widths(board.width(), cell_width))
         .column_spacing(0)
 }
 
-fn next_piece_widget(game: &Game) -> Table {
-    let color = game.piece_color(&game.next_piece);
-    let bbox: Matrix<Cell> = generate_bounding_matrix(&game.next_piece, color);
+// Two columns a cell draws "[]" on the cell's color. Narrow boards
+// get one column a cell, with blocks in the cell's color instead.
+fn filled_cell(cell: &Cell, cell_width: u16) -> TuiCell<'static> {
+    if cell_width == 1 {
+        TuiCell::from("█").style(Style::default().fg(cell_color(cell)))
+    } else {
+        TuiCell::from("[]").style(Style::default().bg(cell_color(cell)))
+    }
+}
+
+fn clearing_cell(cell_width: u16) -> TuiCell<'static> {
+    let symbol = if cell_width == 1 { "█" } else { "[]" };
+    TuiCell::from(symbol).style(Style::default().fg(TuiColor::Black).bg(TuiColor::White))
+}
+
+fn ghost_cell(color: u8, cell_width: u16) -> TuiCell<'static> {
+    let symbol = if cell_width == 1 { "░" } else { "[]" };
+    TuiCell::from(symbol).style(Style::default().fg(TuiColor::Indexed(color)))
+}
+
+fn hint_cell(color: u8, cell_width: u16) -> TuiCell<'static> {
+    let symbol = if cell_width == 1 { "▚" } else { "<>" };
+    TuiCell::from(symbol).style(Style::default().fg(TuiColor::Indexed(color)).add_modifier(Modifier::BOLD))
+}
+
+fn empty_cell(cell_width: u16) -> TuiCell<'static> {
+    TuiCell::from(if cell_width == 1 { " " } else { "  " })
+}
+
+/// An upcoming piece centered in a box, or an empty box when previews are off
+pub fn preview_widget<'a>(game: &Game, piece: Option<&Piece>, title: &'a str, cell_width: u16) -> Table<'a> {
+    let bbox: Option<Matrix<Cell>> = piece
+        .map(|piece| generate_bounding_matrix(piece, game.piece_color(piece)));
+    let (width, height) = bbox.as_ref().map_or((0, 0), |bbox| (bbox.width(), bbox.height()));
+    // center the piece within the widget's borders
+    let box_width = if cell_width == 1 { COMPACT_WIDGET_WIDTH } else { LEFT_WIDGET_WIDTH };
+    let left = (box_width - 4).saturating_sub(width as u16 * cell_width) / 2;
+    let top = (STATS_HEIGHT - 1).saturating_sub(height as u16) / 2;
     let table_block = Block::default()
         .borders(Borders::ALL)
-        .padding(Padding { left: 6, right: 0, top: 1, bottom: 0 })
-        .title("Next Piece")
+        .padding(Padding { left, right: 0, top, bottom: 0 })
+        .title(title)
         .title_alignment(Alignment::Center)
         .border_type(BorderType::Thick)
         .border_style(Style::default().fg(TuiColor::White));
     let rows = bbox
-        .rows
         .iter()
-        .rev()
+        .flat_map(|bbox| bbox.rows.iter().rev())
         .map(|row| {
             let cells = row
                 .iter()
                 .map(|cell| {
                     if cell.value == Color::White {
-                        TuiCell::from("  ")
+                        empty_cell(cell_width)
                     } else {
-                        TuiCell::from("[]")
-                            .style(Style::default().bg(cell_color(cell)))
+                        filled_cell(cell, cell_width)
                     }
-                });
+                })
+                .collect::<Vec<_>>();
             Row::new(cells).height(1)
-        });
+        })
+        .collect::<Vec<_>>();
     Table::new(rows)
         .block(table_block)
-        .widths(&[
-            Constraint::Length(2),
-            Constraint::Length(2),
-            Constraint::Length(2),
-            Constraint::Length(2),
-        ])
+        .widths(cell_widths(width, cell_width))
         .column_spacing(0)
 }
 
+// Every bound action in a few words, then the keys the game keeps for itself
+fn legend_rows(keys: &KeyBindings, labels: fn(Action) -> &'static str) -> Vec<Row<'static>> {
+    let bound = Action::all().into_iter().map(|action| {
+        let names = keys.keys_for(action).iter().map(|key| key_name(*key)).collect::<Vec<_>>();
+        (labels(action), names.join(", "))
+    });
+    let fixed = [
+        ("Save/quit", key_name(QUIT_KEY)),
+        ("Help", key_name(HELP_KEY)),
+        ("Legend", key_name(LEGEND_KEY)),
+    ];
+    bound
+        .chain(fixed)
+        .map(|(label, keys)| Row::new(vec![TuiCell::from(label), TuiCell::from(keys)]))
+        .collect()
+}
+
+fn short_label(action: Action) -> &'static str {
+    match action {
+        Action::MoveLeft => "Left",
+        Action::MoveRight => "Right",
+        Action::SoftDrop => "Drop",
+        Action::RotateClockwise => "Rotate",
+        Action::RotateCounterclockwise => "Rotate ccw",
+        Action::Rotate180 => "Rotate 180",
+        Action::Pause => "Pause",
+    }
+}
+
+fn legend_widget(keys: &KeyBindings) -> Table<'static> {
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_type(BorderType::Thick)
+        .title("Controls")
+        .title_alignment(Alignment::Center);
+    Table::new(legend_rows(keys, short_label))
+        .block(block)
+        .style(Style::default().fg(TuiColor::Indexed(245)))
+        .widths(&[Constraint::Length(10), Constraint::Length(9)])
+}
+
+fn collapsed_legend_widget() -> Paragraph<'static> {
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_type(BorderType::Thick)
+        .title("Controls")
+        .title_alignment(Alignment::Center);
+    Paragraph::new(format!("{} to show", key_name(LEGEND_KEY)))
+        .block(block)
+        .alignment(Alignment::Center)
+        .style(Style::default().fg(TuiColor::Indexed(245)))
+}
+
+fn help_widget(keys: &KeyBindings) -> Table<'static> {
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_type(BorderType::Double)
+        .padding(Padding { left: 2, right: 2, top: 1, bottom: 1 })
+        .title("Help - paused")
+        .title_alignment(Alignment::Center)
+        .style(Style::default().fg(TuiColor::Indexed(185)));
+    let mut rows = legend_rows(keys, |action| action.label());
+    rows.push(Row::new(vec!["Quit without saving", "Ctrl-c"]));
+    Table::new(rows)
+        .header(
+            Row::new(vec![String::from("Back to the game"), format!("{}, Esc", key_name(HELP_KEY))])
+                .style(Style::default().add_modifier(Modifier::BOLD))
+                .bottom_margin(1)
+        )
+        .block(block)
+        .widths(&[Constraint::Length(26), Constraint::Length(14)])
+}
+
+fn cell_widths(columns: usize, cell_width: u16) -> &'static [Constraint] {
+    const NARROW: [Constraint; 32] = [Constraint::Length(1); 32];
+    const WIDE: [Constraint; 32] = [Constraint::Length(2); 32];
+    let widths = if cell_width == 1 { &NARROW } else { &WIDE };
+    &widths[..columns.min(widths.len())]
+}
+
 fn cell_color(cell: &Cell) -> TuiColor {
     TuiColor::Indexed(cell.color)
 }
@@ -206,19 +586,19 @@ fn generate_bounding_matrix(piece: &Piece, color: u8) -> Matrix<Cell> {
     let (lower_left, upper_right) = piece.bounds();
     let width = upper_right.x - lower_left.x + 1;
     let height = upper_right.y - lower_left.y + 1;
-    let mut out: Matrix<Cell> = Matrix::new(width, height, Cell::white());
+    let mut out: Matrix<Cell> = Matrix::new(width as usize, height as usize, Cell::white());
     piece
         .points
         .iter()
         .for_each(|p| {
-            let x = p.x - lower_left.x;
-            let y = p.y - lower_left.y;
-            out.set(x, y, Cell::black(color));
+            let relative = Point::new(p.x - lower_left.x, p.y - lower_left.y);
+            out.set_at(&relative, Cell::black(color));
         });
     out
 }
 
-fn score_widget(stats: &Stats) -> Paragraph {
+/// The score in a box
+pub fn score_widget(stats: &Stats) -> Paragraph<'_> {
     let block = Block::default()
         .borders(Borders::ALL)
         .border_type(BorderType::Thick)
@@ -233,7 +613,8 @@ fn score_widget(stats: &Stats) -> Paragraph {
         .style(style)
 }
 
-fn level_widget(level: &Level) -> Paragraph {
+/// The level number in a box
+pub fn level_widget(level: &Level) -> Paragraph<'_> {
     let block = Block::default()
         .borders(Borders::ALL)
         .border_type(BorderType::Thick)
@@ -248,7 +629,8 @@ fn level_widget(level: &Level) -> Paragraph {
         .style(style)
 }
 
-fn lines_widget(stats: &Stats) -> Paragraph {
+/// The rows cleared in a box
+pub fn lines_widget(stats: &Stats) -> Paragraph<'_> {
     let block = Block::default()
         .borders(Borders::ALL)
         .border_type(BorderType::Thick)
@@ -263,7 +645,30 @@ fn lines_widget(stats: &Stats) -> Paragraph {
         .style(style)
 }
 
-fn banner_widget(game: &Game) -> Paragraph {
+fn compact_stats_widget(game: &Game) -> Paragraph<'_> {
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_type(BorderType::Thick);
+    let status = if game.game_over {
+        "GAME OVER"
+    } else if game.paused {
+        "PAUSED"
+    } else {
+        ""
+    };
+    let content = format!(
+        "Score\n{}\n\nLevel\n{}\n\nLines\n{}\n\n{status}",
+        game.stats.score,
+        game.level.number,
+        game.stats.rows_cleared,
+    );
+    Paragraph::new(content)
+        .block(block)
+        .alignment(Alignment::Center)
+        .style(Style::default().add_modifier(Modifier::BOLD))
+}
+
+fn banner_widget(game: &Game) -> Paragraph<'_> {
     let block = Block::default()
         .borders(Borders::ALL)
         .border_type(BorderType::Thick);
@@ -286,3 +691,129 @@ fn banner_widget(game: &Game) -> Paragraph {
         .block(block);
     paragraph
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        config::{Config, GameMode},
+        ui::snapshot::{assert_snapshot, render},
+    };
+
+    fn game_in_progress() -> Game {
+        let mut game = Game::new(Config { seed: Some(3), preview: 2, ..Config::default() });
+        (0..400).for_each(|_| game.on_tick());
+        game
+    }
+
+    #[test]
+    fn test_arrangements() {
+        let keys = KeyBindings::default();
+        let hud = Hud { keys: Some(&keys), show_legend: true, show_help: false, ..Hud::default() };
+        let mut game = game_in_progress();
+        let sizes = [
+            (80, 24, Arrangement::Full),
+            (60, 24, Arrangement::NoRight),
+            (30, 24, Arrangement::Compact),
+            (25, 24, Arrangement::TooSmall),
+            (80, 20, Arrangement::TooSmall),
+        ];
+        for (width, height, expected) in sizes {
+            assert_eq!(arrange(Rect::new(0, 0, width, height), &game, LEFT_WIDGET_WIDTH), expected);
+            let screen = render(width, height, |f| draw(f, &mut game, &hud));
+            assert_snapshot(&format!("game_{width}x{height}"), &screen);
+        }
+    }
+
+    // Shifts the piece sideways then drops it until the next one comes in
+    fn drop_piece(game: &mut Game, shift: i32) {
+        for _ in 0..shift.abs() {
+            if shift < 0 { game.on_left() } else { game.on_right() }
+        }
+        let next = game.next_piece.clone();
+        loop {
+            game.on_down();
+            if game.current_piece == next || game.game_over { break }
+        }
+    }
+
+    fn snapshot(name: &str, game: &mut Game, hud: &Hud) {
+        assert_snapshot(name, &render(80, 32, |f| draw(f, game, hud)));
+    }
+
+    #[test]
+    fn test_board_with_pieces() {
+        let keys = KeyBindings::default();
+        let hud = Hud { keys: Some(&keys), show_legend: true, show_help: false, ..Hud::default() };
+        let mut game = Game::new(Config { seed: Some(11), preview: 3, ghost: true, ..Config::default() });
+        for shift in [-4, -2, 0, 2, 4, -3, 3, -1, 1] {
+            drop_piece(&mut game, shift);
+        }
+        game.on_right();
+        snapshot("game_board_with_pieces", &mut game, &hud);
+
+        let mut game = Game::new(Config { seed: Some(11), game_mode: GameMode::BType, ..Config::default() });
+        snapshot("game_b_type", &mut game, &Hud::default());
+    }
+
+    #[test]
+    fn test_banners() {
+        let mut game = Game::new(Config { seed: Some(5), ..Config::default() });
+        drop_piece(&mut game, 2);
+        game.toggle_pause();
+        snapshot("game_paused", &mut game, &Hud::default());
+
+        let mut game = Game::new(Config { seed: Some(5), ..Config::default() });
+        while !game.game_over {
+            drop_piece(&mut game, 0);
+        }
+        snapshot("game_over", &mut game, &Hud::default());
+    }
+
+    #[test]
+    fn test_legend_and_help() {
+        let keys = crate::input::Preset::Guideline.bindings();
+        let mut game = Game::new(Config { seed: Some(5), ..Config::default() });
+        let mut hud = Hud { keys: Some(&keys), show_legend: false, show_help: false, ..Hud::default() };
+        snapshot("game_legend_collapsed", &mut game, &hud);
+        hud.show_help = true;
+        snapshot("game_help", &mut game, &hud);
+    }
+
+    #[test]
+    fn test_hint_and_pause_menu() {
+        let keys = KeyBindings::default();
+        let mut game = Game::new(Config { seed: Some(5), ghost: true, ..Config::default() });
+        drop_piece(&mut game, -3);
+        let hint = crate::ai::Hint::new(&game, &crate::ai::Weights::default()).unwrap();
+        let mut menu = PauseMenu { hints: true, ..PauseMenu::default() };
+        let hud = Hud { keys: Some(&keys), hint: Some(&hint), ..Hud::default() };
+        snapshot("game_hint", &mut game, &hud);
+
+        menu.next();
+        game.toggle_pause();
+        let hud = Hud { keys: Some(&keys), pause_menu: Some(&menu), ..Hud::default() };
+        snapshot("game_pause_menu", &mut game, &hud);
+    }
+
+    #[test]
+    fn test_hint_text() {
+        let keys = KeyBindings::default();
+        let actions = [Action::MoveLeft, Action::MoveLeft, Action::RotateClockwise, Action::SoftDrop, Action::SoftDrop];
+        let text = hint_text(&actions, None);
+        assert_eq!(text, format!("Hint: {} x2, {}, hold {}", short_label(Action::MoveLeft), short_label(Action::RotateClockwise), short_label(Action::SoftDrop)));
+        assert!(hint_text(&actions, Some(&keys)).starts_with("Hint: "));
+    }
+
+    #[test]
+    fn test_draws_at_any_size() {
+        let keys = KeyBindings::default();
+        let hud = Hud { keys: Some(&keys), show_legend: true, show_help: true, ..Hud::default() };
+        let mut game = game_in_progress();
+        for width in (0..=90).step_by(3) {
+            for height in (0..=40).step_by(2) {
+                render(width, height, |f| draw(f, &mut game, &hud));
+            }
+        }
+    }
+}
@@ -0,0 +1,135 @@
+use ratatui::{
+    backend::Backend,
+    layout::{Alignment, Constraint},
+    style::{Color as TuiColor, Modifier, Style},
+    widgets::{
+        Block,
+        Borders,
+        BorderType,
+        Cell as TuiCell,
+        Clear,
+        Paragraph,
+        Row,
+        Table,
+    },
+    Frame,
+};
+
+use crate::{
+    config::GameMode,
+    high_scores::{HighScores, MAX_NAME_LENGTH},
+};
+
+const TABLE_WIDTH: u16 = 44;
+const TABLE_HEIGHT: u16 = 15;
+const PROMPT_WIDTH: u16 = 34;
+const PROMPT_HEIGHT: u16 = 7;
+
+// The high score table for a mode and starting level, shown full
+// screen after a game and from the config menu.
+pub fn draw_high_scores<B: Backend>(
+    f: &mut Frame<B>,
+    high_scores: &HighScores,
+    mode: GameMode,
+    level: usize,
+) {
+    let size = f.size();
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title("TTrYs")
+        .title_alignment(Alignment::Center)
+        .border_type(BorderType::Double);
+    f.render_widget(block, size);
+
+    let target = super::centered(size, TABLE_WIDTH, TABLE_HEIGHT);
+    f.render_widget(high_scores_widget(high_scores, mode, level), target);
+}
+
+// A prompt for the player's name, drawn over the finished game
+pub fn draw_name_entry<B: Backend>(f: &mut Frame<B>, name: &str, score: usize) {
+    let target = super::centered(f.size(), PROMPT_WIDTH, PROMPT_HEIGHT);
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_type(BorderType::Thick)
+        .title("New High Score!")
+        .title_alignment(Alignment::Center)
+        .style(Style::default().fg(TuiColor::Indexed(220)));
+    let cursor = if name.len() < MAX_NAME_LENGTH { "_" } else { "" };
+    let content = format!("\n{score}\n\nName: {name}{cursor}");
+    let prompt = Paragraph::new(content)
+        .block(block)
+        .alignment(Alignment::Center)
+        .style(Style::default().add_modifier(Modifier::BOLD));
+
+    f.render_widget(Clear, target);
+    f.render_widget(prompt, target);
+}
+
+fn high_scores_widget(high_scores: &HighScores, mode: GameMode, level: usize) -> Table<'static> {
+    let mode_name = match mode {
+        GameMode::AType => "A Type",
+        GameMode::BType => "B Type",
+    };
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_type(BorderType::Thick)
+        .title(format!("High Scores - {mode_name}, Level {level}"))
+        .title_alignment(Alignment::Center)
+        .style(Style::default().fg(TuiColor::Indexed(220)));
+    let header = Row::new(vec!["", "Name", "Score", "Lines"])
+        .style(Style::default().add_modifier(Modifier::BOLD))
+        .bottom_margin(1);
+    let rows = high_scores
+        .top(mode, level)
+        .into_iter()
+        .enumerate()
+        .map(|(i, entry)| {
+            Row::new(vec![
+                TuiCell::from(format!("{}.", i + 1)),
+                TuiCell::from(entry.name.clone()),
+                TuiCell::from(entry.score.to_string()),
+                TuiCell::from(entry.lines.to_string()),
+            ])
+        })
+        .collect::<Vec<_>>();
+
+    Table::new(rows)
+        .header(header)
+        .block(block)
+        .widths(&[
+            Constraint::Length(4),
+            Constraint::Length(MAX_NAME_LENGTH as u16 + 2),
+            Constraint::Length(12),
+            Constraint::Length(8),
+        ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        high_scores::HighScore,
+        ui::snapshot::{assert_snapshot, render},
+    };
+
+    #[test]
+    fn test_table_and_name_entry() {
+        let mut high_scores = HighScores::default();
+        for (name, score) in [("ada", 4200), ("grace", 12800), ("linus", 300)] {
+            high_scores.insert(HighScore {
+                name: String::from(name),
+                score,
+                lines: score / 100,
+                mode: GameMode::BType,
+                level: 3,
+            });
+        }
+        let screen = render(80, 24, |f| draw_high_scores(f, &high_scores, GameMode::BType, 3));
+        assert_snapshot("high_scores", &screen);
+        let screen = render(80, 24, |f| draw_high_scores(f, &high_scores, GameMode::AType, 3));
+        assert_snapshot("high_scores_empty", &screen);
+
+        let screen = render(80, 24, |f| draw_name_entry(f, "Mar", 5000));
+        assert_snapshot("name_entry", &screen);
+    }
+}
@@ -0,0 +1,154 @@
+use ratatui::{
+    backend::Backend,
+    layout::{Alignment, Constraint, Direction, Layout, Margin},
+    style::{Color as TuiColor, Modifier, Style},
+    text::{Span, Spans, Text},
+    widgets::{Block, Borders, BorderType, Paragraph},
+    Frame,
+};
+
+use crate::{game::Game, versus::{Outcome, Versus}};
+use super::draw_game::{
+    draw_game_board,
+    preview_widget,
+    COMPACT_WIDGET_WIDTH,
+    FRAME,
+    LEFT_WIDGET_WIDTH,
+    STATS_HEIGHT,
+};
+
+const METER_WIDTH: u16 = 1;
+
+/// Both players' games side by side, each with its next piece and stats
+pub fn draw_versus<B: Backend>(f: &mut Frame<B>, versus: &Versus) {
+    let size = f.size();
+    let game = &versus.games[0];
+    let (columns, rows) = (game.board.width() as u16, game.board.height() as u16);
+    let board_height = rows + 2;
+    // the same arrangements as one game, halved, with a garbage meter each
+    let full = FRAME + 2 * (LEFT_WIDGET_WIDTH + METER_WIDTH + columns * 2 + 2);
+    let compact = FRAME + 2 * (COMPACT_WIDGET_WIDTH + METER_WIDTH + columns + 2);
+    let (side_width, cell_width) = if size.width >= full {
+        (LEFT_WIDGET_WIDTH, 2)
+    } else {
+        (COMPACT_WIDGET_WIDTH, 1)
+    };
+    if size.width < compact || size.height < FRAME + board_height {
+        super::draw_too_small(f, compact, FRAME + board_height);
+        return
+    }
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title("TTrYs Versus")
+        .title_alignment(Alignment::Center)
+        .border_type(BorderType::Double);
+    f.render_widget(block, size);
+
+    let halves = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Ratio(1, 2), Constraint::Ratio(1, 2)].as_ref())
+        .split(size);
+    let board_width = columns * cell_width + 2;
+    for (player, half) in halves.iter().enumerate() {
+        let area = super::centered(*half, side_width + METER_WIDTH + board_width, board_height);
+        let chunks = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([
+                Constraint::Length(side_width),
+                Constraint::Length(METER_WIDTH),
+                Constraint::Length(board_width),
+            ].as_ref())
+            .split(area);
+        let game = &versus.games[player];
+        draw_game_board(f, chunks[2], game, None, cell_width);
+        // level with the inside of the board
+        let meter = chunks[1].inner(&Margin { vertical: 1, horizontal: 0 });
+        f.render_widget(garbage_meter_widget(game.garbage.rows(), meter.height), meter);
+
+        let side = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(STATS_HEIGHT), Constraint::Min(0)].as_ref())
+            .split(chunks[0]);
+        let shown = if game.preview > 0 { Some(&game.next_piece) } else { None };
+        f.render_widget(preview_widget(game, shown, "Next", cell_width), side[0]);
+        f.render_widget(player_widget(versus, player, game), side[1]);
+    }
+}
+
+// The garbage waiting to come up, as a bar rising from the bottom of the
+// board a cell for each row. It turns red once it's full.
+fn garbage_meter_widget<'a>(rows: usize, height: u16) -> Paragraph<'a> {
+    let filled = rows.min(height as usize);
+    let color = if rows >= height as usize { TuiColor::Red } else { TuiColor::Yellow };
+    let lines: Vec<Spans> = (0..height as usize)
+        .map(|line| if line + filled >= height as usize {
+            Spans::from(Span::styled("█", Style::default().fg(color)))
+        } else {
+            Spans::from("")
+        })
+        .collect();
+    Paragraph::new(lines)
+}
+
+fn player_widget<'a>(versus: &Versus, player: usize, game: &'a Game) -> Paragraph<'a> {
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_type(BorderType::Thick);
+    let (status, color) = if versus.winner() == Some(player) {
+        ("WINS", TuiColor::Green)
+    } else if versus.outcome == Some(Outcome::Draw) {
+        ("DRAW", TuiColor::Yellow)
+    } else if versus.is_over() {
+        ("TOPPED OUT", TuiColor::Red)
+    } else if versus.is_paused() {
+        ("PAUSED", TuiColor::Yellow)
+    } else {
+        ("", TuiColor::Reset)
+    };
+    let content = format!(
+        "Player {}\n\nScore\n{}\n\nLines\n{}\n\nGarbage\n{}\n\n",
+        player + 1,
+        game.stats.score,
+        game.stats.rows_cleared,
+        game.garbage.rows(),
+    );
+    let mut text = Text::from(content);
+    text.extend([Spans::from(Span::styled(status, Style::default().fg(color)))]);
+    Paragraph::new(text)
+        .block(block)
+        .alignment(Alignment::Center)
+        .style(Style::default().add_modifier(Modifier::BOLD))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        config::Config,
+        game::action::Action,
+        ui::snapshot::{assert_snapshot, render},
+    };
+
+    #[test]
+    fn test_versus() {
+        let mut versus = Versus::new(Config { seed: Some(4), ..Config::default() });
+        versus.games[1].add_garbage(2, 3);
+        for _ in 0..30 {
+            versus.on_action(0, Action::MoveLeft);
+            versus.on_action(1, Action::SoftDrop);
+            versus.on_tick();
+        }
+        versus.games[0].add_garbage(5, 1);
+        assert_snapshot("versus_80x24", &render(80, 24, |f| draw_versus(f, &versus)));
+        assert_snapshot("versus_100x24", &render(100, 24, |f| draw_versus(f, &versus)));
+
+        versus.games[0].add_garbage(20, 0);
+        while !versus.is_over() {
+            versus.on_action(0, Action::SoftDrop);
+            versus.on_tick();
+        }
+        assert_snapshot("versus_over", &render(60, 24, |f| draw_versus(f, &versus)));
+        render(40, 10, |f| draw_versus(f, &versus));
+    }
+}
@@ -0,0 +1,39 @@
+use std::{env, fs, path::PathBuf};
+use ratatui::{backend::TestBackend, Frame, Terminal};
+
+// Draws into an in-memory terminal and returns what's on it, one line a
+// row with the trailing blanks trimmed. Colors and styles are left out.
+pub fn render<F: FnOnce(&mut Frame<TestBackend>)>(width: u16, height: u16, draw: F) -> String {
+    let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+    terminal.draw(draw).unwrap();
+    let buffer = terminal.backend().buffer();
+    (0..height)
+        .map(|y| {
+            let row: String = (0..width).map(|x| buffer.get(x, y).symbol.as_str()).collect();
+            format!("{}\n", row.trim_end())
+        })
+        .collect()
+}
+
+// Compares against src/ui/snapshots/<name>.txt. With UPDATE_SNAPSHOTS set
+// they're all written out instead, new ones included, so they can be
+// looked over and committed. Without it a missing one fails, so a renamed
+// test or a lost file can't pass unchecked.
+pub fn assert_snapshot(name: &str, actual: &str) {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("src/ui/snapshots")
+        .join(format!("{name}.txt"));
+    if env::var_os("UPDATE_SNAPSHOTS").is_some() {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, actual).unwrap();
+        return
+    }
+    let Ok(expected) = fs::read_to_string(&path) else {
+        panic!("snapshot {name} is missing, run with UPDATE_SNAPSHOTS=1 to write it\nfound:\n{actual}")
+    };
+    assert!(
+        expected == actual,
+        "snapshot {name} doesn't match, run with UPDATE_SNAPSHOTS=1 if the change is intended\n\
+         expected:\n{expected}\nfound:\n{actual}",
+    );
+}
@@ -0,0 +1,241 @@
+//! Two players on one keyboard, each with a game of their own.
+//!
+//! Both games are dealt from the same seed, so the players get the same
+//! pieces in the same order. Clearing lines sends garbage, by the
+//! [`AttackTable`], to the other player's queue. It first cancels out any
+//! garbage waiting in the sender's own queue, and what's left comes up
+//! under the other board once they lock a piece without clearing. The
+//! first to top out loses, and both topping out on the same tick is a draw.
+
+use rand::{thread_rng, Rng, SeedableRng};
+use rand_pcg::Pcg64;
+
+use crate::{
+    config::Config,
+    game::{
+        action::Action,
+        garbage::{AttackTable, Garbage},
+    },
+    Game,
+    GameEvent,
+};
+
+/// How garbage is sent and how it comes up
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rules {
+    pub attack_table: AttackTable,
+    /// The chance, from 0 to 1, of each garbage row's hole moving
+    /// from the one below it
+    pub messiness: f64,
+}
+
+impl Default for Rules {
+    fn default() -> Self {
+        Self { attack_table: AttackTable::default(), messiness: 0.3 }
+    }
+}
+
+/// How a match ended
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Outcome {
+    Winner(usize),
+    Draw,
+}
+
+/// A versus match between two players
+pub struct Versus {
+    pub games: [Game; 2],
+    // the actions each player pressed since the last tick
+    inputs: [Vec<Action>; 2],
+    attack_table: AttackTable,
+    // picks where the holes in the garbage go, apart from the games'
+    // own draws so the pieces stay the same on both sides
+    rng: Pcg64,
+    pub outcome: Option<Outcome>,
+}
+
+impl Versus {
+    /// Sets up both games from `config`, with one seed between them,
+    /// and the default [`Rules`]
+    pub fn new(config: Config) -> Self {
+        Self::with_rules(config, Rules::default())
+    }
+
+    pub fn with_rules(config: Config, rules: Rules) -> Self {
+        let seed = config.seed.unwrap_or_else(|| thread_rng().gen());
+        let game = |_| {
+            let mut game = Game::new(Config { seed: Some(seed), ..config.clone() });
+            game.garbage.messiness = rules.messiness;
+            game
+        };
+        Self {
+            games: [0, 1].map(game),
+            inputs: [vec![], vec![]],
+            attack_table: rules.attack_table,
+            rng: Pcg64::seed_from_u64(seed),
+            outcome: None,
+        }
+    }
+
+    /// Takes an action from `player` (0 or 1), to be played on the next
+    /// tick. Either player's pause pauses them both.
+    pub fn on_action(&mut self, player: usize, action: Action) {
+        if self.is_over() { return }
+        if action == Action::Pause {
+            self.games.iter_mut().for_each(Game::toggle_pause);
+        } else {
+            self.inputs[player].push(action);
+        }
+    }
+
+    /// Plays a tick of both games, then sends the garbage from their clears
+    /// across, so neither player's attack lands before the other has moved
+    pub fn on_tick(&mut self) {
+        if self.is_over() { return }
+        let attacks = [0, 1].map(|player| {
+            let inputs = std::mem::take(&mut self.inputs[player]);
+            let attack: usize = self.games[player]
+                .step(&inputs)
+                .iter()
+                .map(|event| match event {
+                    GameEvent::Cleared { clear } => self.attack_table.attack(clear),
+                    _ => 0,
+                })
+                .sum();
+            self.games[player].garbage.cancel(attack)
+        });
+        for (player, rows) in attacks.into_iter().enumerate() {
+            if rows > 0 {
+                let opponent = &mut self.games[1 - player];
+                let hole = self.rng.gen_range(0..opponent.board.width());
+                opponent.garbage.push(Garbage { rows, hole });
+            }
+        }
+        self.outcome = match (self.games[0].game_over, self.games[1].game_over) {
+            (true, true) => Some(Outcome::Draw),
+            (true, false) => Some(Outcome::Winner(1)),
+            (false, true) => Some(Outcome::Winner(0)),
+            (false, false) => None,
+        };
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.outcome.is_some()
+    }
+
+    pub fn winner(&self) -> Option<usize> {
+        match self.outcome {
+            Some(Outcome::Winner(winner)) => Some(winner),
+            _ => None,
+        }
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.games[0].paused
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{
+        garbage::Clear,
+        matrix::{Cell, Color},
+    };
+
+    #[test]
+    fn test_same_pieces() {
+        let mut versus = Versus::new(Config { seed: Some(9), preview: 3, ..Config::default() });
+        let [one, two] = &versus.games;
+        assert_eq!(one.current_piece, two.current_piece);
+        assert_eq!(one.upcoming, two.upcoming);
+
+        versus.on_action(1, Action::Pause);
+        assert!(versus.is_paused() && versus.games[1].paused);
+    }
+
+    // Fills in the rows each of `players`' pieces will land in, all but the
+    // piece's own cells, and drops them together
+    fn clear_lines(versus: &mut Versus, players: &[usize]) -> usize {
+        let mut lines = 0;
+        for player in players {
+            let ghost = versus.games[*player].ghost_piece();
+            let rows: std::collections::HashSet<_> = ghost.points.iter().map(|p| p.y).collect();
+            let board = &mut versus.games[*player].board;
+            for y in &rows {
+                for x in 0..board.width() as i32 {
+                    let p = crate::game::piece::Point::new(x, *y);
+                    if !ghost.points.contains(&p) {
+                        board.set_at(&p, Cell::black(1));
+                    }
+                }
+            }
+            lines = rows.len();
+        }
+        let pieces = versus.games[players[0]].stats.pieces;
+        while versus.games[players[0]].stats.pieces == pieces {
+            players.iter().for_each(|player| versus.on_action(*player, Action::SoftDrop));
+            versus.on_tick();
+        }
+        lines
+    }
+
+    #[test]
+    fn test_clears_send_garbage() {
+        let rules = Rules { messiness: 0.0, ..Rules::default() };
+        let mut versus = Versus::with_rules(Config { seed: Some(9), ..Config::default() }, rules);
+        let lines = clear_lines(&mut versus, &[0]);
+        assert_eq!(versus.games[0].stats.rows_cleared, lines);
+        let clear = Clear { lines, all_clear: versus.games[0].board.rows.iter().flatten().all(|cell| cell.value != Color::Black), ..Clear::default() };
+        assert_eq!(versus.games[1].garbage.rows(), AttackTable::default().attack(&clear));
+    }
+
+    #[test]
+    fn test_attacks_cancel_incoming_garbage() {
+        let mut versus = Versus::new(Config { seed: Some(9), ..Config::default() });
+        versus.games[0].add_garbage(30, 0);
+        clear_lines(&mut versus, &[0]);
+        assert!(versus.games[0].garbage.rows() < 30);
+        assert_eq!(versus.games[1].garbage.rows(), 0);
+        assert!(!versus.games[0].game_over);
+    }
+
+    #[test]
+    fn test_first_to_top_out_loses() {
+        let mut versus = Versus::new(Config { seed: Some(9), ..Config::default() });
+        versus.games[1].add_garbage(20, 0);
+        while !versus.is_over() {
+            versus.on_action(1, Action::SoftDrop);
+            versus.on_tick();
+        }
+        assert_eq!(versus.winner(), Some(0));
+        assert!(!versus.games[0].game_over);
+        let pieces = versus.games[0].stats.pieces;
+        versus.on_action(0, Action::SoftDrop);
+        versus.on_tick();
+        assert_eq!(versus.games[0].stats.pieces, pieces);
+    }
+
+    #[test]
+    fn test_attacks_on_the_same_tick_both_land() {
+        let rules = Rules { messiness: 0.0, ..Rules::default() };
+        let mut versus = Versus::with_rules(Config { seed: Some(9), ..Config::default() }, rules);
+        clear_lines(&mut versus, &[0, 1]);
+        let [one, two] = &versus.games;
+        assert!(one.garbage.rows() > 0);
+        assert_eq!(one.garbage.rows(), two.garbage.rows());
+    }
+
+    #[test]
+    fn test_topping_out_together_is_a_draw() {
+        let mut versus = Versus::new(Config { seed: Some(9), ..Config::default() });
+        versus.games.iter_mut().for_each(|game| game.add_garbage(20, 0));
+        while !versus.is_over() {
+            versus.on_action(0, Action::SoftDrop);
+            versus.on_action(1, Action::SoftDrop);
+            versus.on_tick();
+        }
+        assert_eq!(versus.outcome, Some(Outcome::Draw));
+        assert_eq!(versus.winner(), None);
+    }
+}